@@ -0,0 +1,78 @@
+use super::{
+    companion_ai_system::summon_spot, gamelog::GameLog, spawner, statistics::RunStats, Boss,
+    CombatStats, Map, Name, TileType,
+};
+use specs::prelude::*;
+
+const MINIONS_SUMMONED: i32 = 3;
+const ENRAGED_STRENGTH_BONUS: i32 = 3;
+
+/// Bosses change tactics as they get hurt: at half health they call for help,
+/// at a quarter they fly into a rage. Each phase only kicks in once.
+pub fn boss_phases(ecs: &mut World) {
+    let mut phase_changes: Vec<(Entity, i32, String, String)> = Vec::new();
+    {
+        let entities = ecs.entities();
+        let mut bosses = ecs.write_storage::<Boss>();
+        let combat_stats = ecs.read_storage::<CombatStats>();
+        let names = ecs.read_storage::<Name>();
+        for (entity, boss, stats, name) in (&entities, &mut bosses, &combat_stats, &names).join() {
+            let phase = if stats.hp * 4 <= stats.max_hp {
+                3
+            } else if stats.hp * 2 <= stats.max_hp {
+                2
+            } else {
+                1
+            };
+            // a boss taken from full health to a quarter in one hit still gets both
+            while boss.phase < phase && stats.hp > 0 {
+                boss.phase += 1;
+                phase_changes.push((entity, boss.phase, name.name.clone(), boss.minion.clone()));
+            }
+        }
+    }
+
+    for (boss, phase, boss_name, minion) in phase_changes {
+        match phase {
+            2 => {
+                ecs.fetch_mut::<GameLog>()
+                    .add(format!("The {} calls for help!", boss_name));
+                for _i in 0..MINIONS_SUMMONED {
+                    if let Some(spot) = summon_spot(ecs, boss) {
+                        spawner::spawn_entity(ecs, spot.x, spot.y, &minion);
+                    }
+                }
+            }
+            _ => {
+                ecs.fetch_mut::<GameLog>()
+                    .add(format!("The {} flies into a rage!", boss_name));
+                if let Some(stats) = ecs.write_storage::<CombatStats>().get_mut(boss) {
+                    stats.strength += ENRAGED_STRENGTH_BONUS;
+                }
+            }
+        }
+    }
+}
+
+/// The boss is dead: the gates of its arena open up, and stay open for good.
+pub fn open_sealed_gates(ecs: &mut World, boss_name: &str) {
+    {
+        let mut map = ecs.fetch_mut::<Map>();
+        unseal_gates(&mut map);
+        ecs.fetch_mut::<RunStats>()
+            .cleared_boss_depths
+            .push(map.depth);
+    }
+    ecs.fetch_mut::<GameLog>().add(format!(
+        "With the {} slain, the gates grind open.",
+        boss_name
+    ));
+}
+
+pub fn unseal_gates(map: &mut Map) {
+    for tile in map.tiles.iter_mut() {
+        if *tile == TileType::SealedGate {
+            *tile = TileType::Floor;
+        }
+    }
+}
@@ -0,0 +1,50 @@
+extern crate specs;
+use super::{Bystander, EntityMoved, Map, Position, RunState};
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+
+/// Townsfolk don't do much: every now and then they take a step in a random direction.
+pub struct BystanderAI {}
+
+impl<'a> System<'a> for BystanderAI {
+    type SystemData = (
+        WriteExpect<'a, Map>,
+        ReadExpect<'a, RunState>,
+        Entities<'a>,
+        ReadStorage<'a, Bystander>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, EntityMoved>,
+        WriteExpect<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut map, runstate, entities, bystander, mut position, mut entity_moved, mut rng) =
+            data;
+
+        // only run system if the state is MonsterTurn
+        if *runstate != RunState::MonsterTurn {
+            return;
+        }
+
+        for (entity, _bystander, pos) in (&entities, &bystander, &mut position).join() {
+            let (delta_x, delta_y) = match rng.roll_dice(1, 8) {
+                1 => (-1, 0),
+                2 => (1, 0),
+                3 => (0, -1),
+                4 => (0, 1),
+                _ => continue,
+            };
+            let destination_idx = map.xy_idx(pos.x + delta_x, pos.y + delta_y);
+            if !map.blocked_tiles[destination_idx] {
+                let idx = map.xy_idx(pos.x, pos.y);
+                map.blocked_tiles[idx] = false;
+                pos.x += delta_x;
+                pos.y += delta_y;
+                entity_moved
+                    .insert(entity, EntityMoved {})
+                    .expect("Unable to insert marker");
+                map.blocked_tiles[destination_idx] = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,219 @@
+// Race and class decide what the player starts out with. The player entity already
+// exists (spawner::player) by the time New Game is picked, so creating the character
+// means reworking that entity according to the choices made.
+use super::{
+    identification::identify_item, spawner, Character, Class, CombatStats, Equippable, Equipped,
+    InBackpack, ManaPool, Name, Race, Viewshed, Wallet,
+};
+use specs::prelude::*;
+
+pub const RACES: [Race; 3] = [Race::Human, Race::Dwarf, Race::Elf];
+pub const CLASSES: [Class; 3] = [Class::Warrior, Class::Rogue, Class::Mage];
+pub const MAX_NAME_LENGTH: usize = 20;
+
+/// The choices made so far on the character creation screens.
+pub struct CharacterDraft {
+    pub race: Race,
+    pub class: Class,
+    pub name: String,
+}
+
+impl CharacterDraft {
+    pub fn new() -> CharacterDraft {
+        CharacterDraft {
+            race: Race::Human,
+            class: Class::Warrior,
+            name: String::new(),
+        }
+    }
+}
+
+impl Default for CharacterDraft {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Where a character starts out, before any equipment.
+struct Attributes {
+    max_hp: i32,
+    strength: i32,
+    defense: i32,
+    max_mana: i32,
+    sight: i32,
+}
+
+pub fn race_name(race: Race) -> &'static str {
+    match race {
+        Race::Human => "Human",
+        Race::Dwarf => "Dwarf",
+        Race::Elf => "Elf",
+    }
+}
+
+pub fn race_description(race: Race) -> &'static str {
+    match race {
+        Race::Human => "Good at nothing in particular, bad at nothing either.",
+        Race::Dwarf => "Tough and stubborn, but short-sighted and no friend of magic.",
+        Race::Elf => "Frail, with keen eyes and a knack for magic.",
+    }
+}
+
+pub fn class_name(class: Class) -> &'static str {
+    match class {
+        Class::Warrior => "Warrior",
+        Class::Rogue => "Rogue",
+        Class::Mage => "Mage",
+    }
+}
+
+pub fn class_description(class: Class) -> &'static str {
+    match class {
+        Class::Warrior => "Longsword, shield and leather armor. No spells.",
+        Class::Rogue => "Dagger, light armor, a fat purse and a confusion spell.",
+        Class::Mage => "Dagger, a health potion, magic missile and mending.",
+    }
+}
+
+fn race_attributes(race: Race) -> Attributes {
+    match race {
+        Race::Human => Attributes {
+            max_hp: 30,
+            strength: 5,
+            defense: 2,
+            max_mana: 10,
+            sight: 8,
+        },
+        Race::Dwarf => Attributes {
+            max_hp: 36,
+            strength: 5,
+            defense: 3,
+            max_mana: 6,
+            sight: 7,
+        },
+        Race::Elf => Attributes {
+            max_hp: 26,
+            strength: 4,
+            defense: 2,
+            max_mana: 14,
+            sight: 10,
+        },
+    }
+}
+
+/// What the class adds on top of the race: (hp, strength, defense, mana).
+fn class_modifiers(class: Class) -> (i32, i32, i32, i32) {
+    match class {
+        Class::Warrior => (8, 2, 0, -4),
+        Class::Rogue => (2, 1, 0, 0),
+        Class::Mage => (-4, 0, 0, 10),
+    }
+}
+
+/// Starting gear; the `bool` says whether it starts out equipped.
+fn starting_items(class: Class) -> Vec<(&'static str, bool)> {
+    match class {
+        Class::Warrior => vec![
+            ("Longsword", true),
+            ("Shield", true),
+            ("Leather Armor", true),
+        ],
+        Class::Rogue => vec![
+            ("Dagger", true),
+            ("Leather Armor", true),
+            ("Leather Boots", true),
+        ],
+        Class::Mage => vec![("Dagger", true), ("Health Potion", false)],
+    }
+}
+
+fn starting_spells(class: Class) -> Vec<&'static str> {
+    match class {
+        Class::Warrior => vec![],
+        Class::Rogue => vec!["Confusion"],
+        Class::Mage => vec!["Magic Missile", "Mending"],
+    }
+}
+
+fn starting_gold(class: Class) -> i32 {
+    match class {
+        Class::Rogue => 100,
+        _ => 50,
+    }
+}
+
+/// Turns the freshly spawned player into the character described by the draft.
+pub fn create_character(ecs: &mut World) {
+    let player = *ecs.fetch::<Entity>();
+    let (race, class, name) = {
+        let draft = ecs.fetch::<CharacterDraft>();
+        (draft.race, draft.class, draft.name.trim().to_string())
+    };
+
+    let attributes = race_attributes(race);
+    let (hp_bonus, strength_bonus, defense_bonus, mana_bonus) = class_modifiers(class);
+    let max_hp = attributes.max_hp + hp_bonus;
+    let max_mana = i32::max(0, attributes.max_mana + mana_bonus);
+
+    ecs.write_storage::<Character>()
+        .insert(player, Character { race, class })
+        .expect("Unable to insert character");
+    ecs.write_storage::<Name>()
+        .insert(
+            player,
+            Name {
+                name: if name.is_empty() {
+                    "Player".to_string()
+                } else {
+                    name
+                },
+            },
+        )
+        .expect("Unable to insert name");
+    ecs.write_storage::<CombatStats>()
+        .insert(
+            player,
+            CombatStats {
+                max_hp,
+                hp: max_hp,
+                strength: attributes.strength + strength_bonus,
+                defense: attributes.defense + defense_bonus,
+            },
+        )
+        .expect("Unable to insert combat stats");
+    if let Some(pool) = ecs.write_storage::<ManaPool>().get_mut(player) {
+        pool.max_mana = max_mana;
+        pool.mana = max_mana;
+    }
+    if let Some(viewshed) = ecs.write_storage::<Viewshed>().get_mut(player) {
+        viewshed.range = attributes.sight;
+        viewshed.dirty = true;
+    }
+    if let Some(wallet) = ecs.write_storage::<Wallet>().get_mut(player) {
+        wallet.gold = starting_gold(class);
+    }
+
+    for (item_name, equip) in starting_items(class) {
+        if let Some(item) = spawner::give_item(ecs, player, item_name) {
+            // You know what you packed yourself
+            identify_item(ecs, item);
+            if equip {
+                equip_item(ecs, player, item);
+            }
+        }
+    }
+    for spell in starting_spells(class) {
+        spawner::learn_spell(ecs, player, spell);
+    }
+}
+
+fn equip_item(ecs: &mut World, owner: Entity, item: Entity) {
+    let slot = match ecs.read_storage::<Equippable>().get(item) {
+        Some(equippable) => equippable.slot,
+        None => return,
+    };
+    ecs.write_storage::<InBackpack>().remove(item);
+    ecs.write_storage::<Equipped>()
+        .insert(item, Equipped { owner, slot })
+        .expect("Unable to insert equipped component");
+}
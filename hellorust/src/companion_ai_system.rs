@@ -0,0 +1,167 @@
+extern crate specs;
+use super::{
+    Companion, EntityMoved, Map, Monster, Point, Position, RunState, TileType, Viewshed,
+    WantsToMelee,
+};
+use specs::prelude::*;
+extern crate rltk;
+
+/// How far companions stray from the player, whether to chase a monster or not.
+const LEASH_RADIUS: f32 = 6.0;
+
+pub struct CompanionAI {}
+
+impl<'a> System<'a> for CompanionAI {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteExpect<'a, Map>,
+        ReadExpect<'a, Point>,
+        ReadExpect<'a, RunState>,
+        Entities<'a>,
+        WriteStorage<'a, Viewshed>,
+        ReadStorage<'a, Companion>,
+        ReadStorage<'a, Monster>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, WantsToMelee>,
+        WriteStorage<'a, EntityMoved>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            mut map,
+            player_pos,
+            runstate,
+            entities,
+            mut viewshed,
+            companion,
+            monster,
+            mut position,
+            mut wants_to_melee,
+            mut entity_moved,
+        ) = data;
+
+        // only run system if the state is MonsterTurn
+        if *runstate != RunState::MonsterTurn {
+            return;
+        }
+
+        let monsters: Vec<(Entity, Point)> = (&entities, &monster, &position)
+            .join()
+            .map(|(entity, _monster, pos)| (entity, Point::new(pos.x, pos.y)))
+            .collect();
+
+        for (entity, viewshed, _companion, pos) in
+            (&entities, &mut viewshed, &companion, &mut position).join()
+        {
+            let here = Point::new(pos.x, pos.y);
+            let distance = |a: Point, b: Point| rltk::DistanceAlg::Pythagoras.distance2d(a, b);
+
+            let destination = if distance(here, *player_pos) > LEASH_RADIUS {
+                // Strayed too far, come back
+                Some(*player_pos)
+            } else {
+                // Go for the closest monster it can see that isn't too far from the player
+                let enemy = monsters
+                    .iter()
+                    .filter(|(_enemy, enemy_pos)| {
+                        viewshed.visible_tiles.contains(enemy_pos)
+                            && distance(*enemy_pos, *player_pos) <= LEASH_RADIUS
+                    })
+                    .min_by(|a, b| {
+                        distance(here, a.1)
+                            .partial_cmp(&distance(here, b.1))
+                            .unwrap()
+                    });
+                match enemy {
+                    Some((enemy, enemy_pos)) if distance(here, *enemy_pos) < 1.5 => {
+                        wants_to_melee
+                            .insert(entity, WantsToMelee { target: *enemy })
+                            .expect("Unable to insert attack");
+                        None
+                    }
+                    Some((_enemy, enemy_pos)) => Some(*enemy_pos),
+                    None if distance(here, *player_pos) > 2.0 => Some(*player_pos),
+                    None => None,
+                }
+            };
+
+            if let Some(destination) = destination {
+                let idx = map.xy_idx(pos.x, pos.y);
+                let goal = map.xy_idx(destination.x, destination.y);
+                // a_star_search won't path onto a blocked tile, and monsters block theirs
+                let goal_blocked = map.blocked_tiles[goal];
+                map.blocked_tiles[goal] = false;
+                let path = rltk::a_star_search(idx as i32, goal as i32, &*map);
+                map.blocked_tiles[goal] = goal_blocked;
+
+                // Stop next to whatever we're heading for
+                if path.success && path.steps.len() > 2 {
+                    map.blocked_tiles[idx] = false;
+                    pos.x = path.steps[1] as i32 % map.width;
+                    pos.y = path.steps[1] as i32 / map.width;
+                    entity_moved
+                        .insert(entity, EntityMoved {})
+                        .expect("Unable to insert marker");
+                    let new_idx = map.xy_idx(pos.x, pos.y);
+                    map.blocked_tiles[new_idx] = true;
+                    viewshed.dirty = true;
+                }
+            }
+        }
+    }
+}
+
+/// The closest walkable tile around `center` that nobody else has claimed yet.
+fn free_tile_near(map: &Map, center: Point, taken: &[Point]) -> Option<Point> {
+    for radius in 1..4 {
+        for y in center.y - radius..=center.y + radius {
+            for x in center.x - radius..=center.x + radius {
+                if x < 1 || y < 1 || x > map.width - 2 || y > map.height - 2 {
+                    continue;
+                }
+                let tile = map.tiles[map.xy_idx(x, y)];
+                let point = Point::new(x, y);
+                if tile != TileType::Wall && !taken.contains(&point) {
+                    return Some(point);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// After a level change the companions tag along: put them down around the player.
+pub fn place_companions_near_player(ecs: &mut World) {
+    let player_pos = *ecs.fetch::<Point>();
+    let map = ecs.fetch::<Map>();
+    let companions = ecs.read_storage::<Companion>();
+    let mut positions = ecs.write_storage::<Position>();
+    let mut viewsheds = ecs.write_storage::<Viewshed>();
+
+    let mut taken: Vec<Point> = vec![player_pos];
+    // monsters and townsfolk have already been spawned
+    for pos in (&positions, !&companions).join().map(|(pos, _)| pos) {
+        taken.push(Point::new(pos.x, pos.y));
+    }
+
+    for (_companion, pos, viewshed) in (&companions, &mut positions, &mut viewsheds).join() {
+        if let Some(spot) = free_tile_near(&map, player_pos, &taken) {
+            pos.x = spot.x;
+            pos.y = spot.y;
+            taken.push(spot);
+        }
+        viewshed.dirty = true;
+    }
+}
+
+/// Where to put a freshly summoned companion: next to whoever summoned it.
+pub fn summon_spot(ecs: &World, summoner: Entity) -> Option<Point> {
+    let summoner_pos = ecs.read_storage::<Position>().get(summoner)?.clone();
+    let map = ecs.fetch::<Map>();
+    let taken: Vec<Point> = ecs
+        .read_storage::<Position>()
+        .join()
+        .map(|pos| Point::new(pos.x, pos.y))
+        .collect();
+    free_tile_near(&map, Point::new(summoner_pos.x, summoner_pos.y), &taken)
+}
@@ -1,4 +1,6 @@
+use crate::identification::MagicItemNames;
 use crate::map::Map;
+use crate::statistics::RunStats;
 use rltk::RGB;
 use serde::{Deserialize, Serialize};
 use specs::error::NoError;
@@ -44,24 +46,6 @@ pub struct WantsToMelee {
     pub target: Entity,
 }
 
-#[derive(Component, Debug, ConvertSaveload, Clone)]
-pub struct SufferDamage {
-    pub amount: Vec<i32>,
-}
-
-impl SufferDamage {
-    pub fn new_damage(store: &mut WriteStorage<SufferDamage>, victim: Entity, amount: i32) {
-        if let Some(suffering) = store.get_mut(victim) {
-            suffering.amount.push(amount);
-        } else {
-            let dmg = SufferDamage {
-                amount: vec![amount],
-            };
-            store.insert(victim, dmg).expect("Unable to insert damage");
-        }
-    }
-}
-
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct Destructable {
     pub broken: bool,
@@ -101,7 +85,12 @@ pub struct Renderable {
 }
 
 #[derive(Component, Serialize, Deserialize, Clone)]
-pub struct Item {}
+pub struct Item {
+    /// in pounds
+    pub weight: f32,
+    /// in gold pieces
+    pub value: i32,
+}
 
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct InBackpack {
@@ -162,11 +151,22 @@ pub struct Confusion {
 pub enum EquipmentSlot {
     Melee,
     Shield,
+    Head,
+    Torso,
+    Legs,
+    Feet,
+    Hands,
+    Ranged,
+    Amulet,
+    /// You have two hands to put rings on, so two of these fit.
+    Ring,
 }
 
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct Equippable {
     pub slot: EquipmentSlot,
+    /// Two-handed weapons go in the Melee slot but leave no hand free for a shield.
+    pub two_handed: bool,
 }
 
 #[derive(Component, ConvertSaveload, Clone)]
@@ -204,7 +204,10 @@ pub struct HungerClock {
 }
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
-pub struct ProvidesFood {}
+pub struct ProvidesFood {
+    /// How many turns eating this keeps you well fed.
+    pub nutrition: i32,
+}
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct MagicMapper {}
@@ -221,6 +224,168 @@ pub struct EntityMoved {}
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct SingleActivation {}
 
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct RandomTeleport {}
+
+/// What a magic item is called until it is identified.
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct ObfuscatedName {
+    pub name: String,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct IdentifiesItem {}
+
+/// Equipment that can't be taken off once it has been put on.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct CursedItem {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesRemoveCurse {}
+
+/// Reusable magic items (wands) spend a charge per use instead of being consumed.
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Charges {
+    pub charges: i32,
+    pub max_charges: i32,
+}
+
+/// Gold carried around by its owner.
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Wallet {
+    pub gold: i32,
+}
+
+/// A heap of coins on the floor; picking it up empties it into your wallet.
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct GoldPile {
+    pub amount: i32,
+}
+
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub enum VendorCategory {
+    General,
+    Weaponsmith,
+    Alchemist,
+}
+
+/// Peaceful NPC that trades out of its backpack. Bump into it to trade.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct Vendor {
+    pub category: VendorCategory,
+}
+
+/// Peaceful townsfolk that mill about and can't be fought.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Bystander {}
+
+/// Magical energy for casting spells. Trickles back in over time.
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct ManaPool {
+    pub mana: i32,
+    pub max_mana: i32,
+    /// Turns left until the next point of mana comes back.
+    pub regen_timer: i32,
+}
+
+/// A spell `owner` has learned. The spell entity carries the same effect
+/// components as the scroll it mimics, and is "used" like an item when cast.
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct KnownSpell {
+    pub owner: Entity,
+    pub mana_cost: i32,
+}
+
+/// Reading this teaches you the named spell.
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct TeachesSpell {
+    pub spell: String,
+}
+
+/// Fights on the player's side and follows them around, even down the stairs.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Companion {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct SummonsCompanion {}
+
+/// What's left of a slain monster. Goes rotten after a while and eventually decays away.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Corpse {
+    /// Turns since death.
+    pub age: i32,
+    /// Percent chance that eating it makes you sick, before it has gone rotten.
+    pub poison_chance: i32,
+}
+
+/// Which of the spawner's loot tables gets rolled when this monster dies.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct LootTable {
+    pub table: String,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub enum RangedAttack {
+    Arrow { damage: i32 },
+    MagicMissile { damage: i32 },
+    Confusion { turns: i32 },
+}
+
+/// Monsters that would rather fight from a distance: they keep the player at arm's length
+/// and shoot or cast whenever their cooldown allows it.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct RangedAttacker {
+    /// Tried in order, the first one that makes sense gets used.
+    pub attacks: Vec<RangedAttack>,
+    pub range: i32,
+    /// Backs away when the player gets closer than this.
+    pub preferred_distance: i32,
+    /// Turns to wait between two attacks.
+    pub cooldown: i32,
+    pub turns_until_ready: i32,
+}
+
+/// The unique monster guarding a milestone depth. Its arena stays sealed until it dies.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Boss {
+    /// What it calls for when the fight turns against it.
+    pub minion: String,
+    /// Goes up as the boss gets hurt, each phase brings a new trick.
+    pub phase: i32,
+}
+
+/// The treasure at the bottom of the dungeon. Bringing it back up to town wins the game.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Artifact {}
+
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum Race {
+    Human,
+    Dwarf,
+    Elf,
+}
+
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum Class {
+    Warrior,
+    Rogue,
+    Mage,
+}
+
+/// Who the player chose to be at the start of the run.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Character {
+    pub race: Race,
+    pub class: Class,
+}
+
+/// The player's level, and how far they've got towards the next one.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Experience {
+    pub level: i32,
+    pub xp: i32,
+}
+
 // "Serialization helper code. We need to implement ConvertSaveload for each type that contains an
 // Entity."
 
@@ -230,4 +395,6 @@ pub struct SerializeMe;
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct SerializationHelper {
     pub map: Map,
+    pub magic_item_names: MagicItemNames,
+    pub run_stats: RunStats,
 }
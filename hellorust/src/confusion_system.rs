@@ -0,0 +1,34 @@
+extern crate specs;
+use crate::components::Confusion;
+use crate::gamelog::GameLog;
+use crate::RunState;
+use specs::prelude::*;
+
+/// Wears off the player's confusion, one turn at a time, whatever they spent the turn on.
+/// Monsters count theirs down in MonsterAI.
+pub struct ConfusionSystem {}
+
+impl<'a> System<'a> for ConfusionSystem {
+    type SystemData = (
+        WriteStorage<'a, Confusion>,
+        ReadExpect<'a, Entity>, // The player
+        ReadExpect<'a, RunState>,
+        WriteExpect<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut confused, player_entity, runstate, mut log) = data;
+
+        if *runstate != RunState::PlayerTurn {
+            return;
+        }
+
+        if let Some(confusion) = confused.get_mut(*player_entity) {
+            confusion.turns -= 1;
+            if confusion.turns < 1 {
+                confused.remove(*player_entity);
+                log.add("You are no longer confused.".to_string());
+            }
+        }
+    }
+}
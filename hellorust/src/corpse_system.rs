@@ -0,0 +1,66 @@
+extern crate specs;
+use crate::components::{Corpse, InBackpack, Name, Renderable};
+use crate::gamelog::GameLog;
+use crate::RunState;
+use specs::prelude::*;
+
+/// After this many turns a corpse has gone off.
+pub const CORPSE_ROTS_AFTER: i32 = 50;
+/// ...and after this many there's nothing left of it.
+const CORPSE_DECAYS_AFTER: i32 = 150;
+
+pub struct CorpseSystem {}
+
+impl<'a> System<'a> for CorpseSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Corpse>,
+        WriteStorage<'a, Name>,
+        WriteStorage<'a, Renderable>,
+        ReadStorage<'a, InBackpack>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, RunState>,
+        WriteExpect<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut corpses,
+            mut names,
+            mut renderables,
+            backpack,
+            player_entity,
+            runstate,
+            mut log,
+        ) = data;
+
+        // Once per round, on the player's turn
+        if *runstate != RunState::PlayerTurn {
+            return;
+        }
+
+        for (entity, corpse, name) in (&entities, &mut corpses, &mut names).join() {
+            corpse.age += 1;
+            let carried_by_player = backpack
+                .get(entity)
+                .is_some_and(|pack| pack.owner == *player_entity);
+
+            if corpse.age == CORPSE_ROTS_AFTER {
+                name.name = format!("rotten {}", name.name);
+                if let Some(renderable) = renderables.get_mut(entity) {
+                    renderable.fg = renderable.fg.to_greyscale();
+                }
+                if carried_by_player {
+                    log.add(format!("Your {} starts to smell.", name.name));
+                }
+            }
+            if corpse.age >= CORPSE_DECAYS_AFTER {
+                if carried_by_player {
+                    log.add(format!("The {} has rotted away.", name.name));
+                }
+                entities.delete(entity).expect("Unable to delete corpse");
+            }
+        }
+    }
+}
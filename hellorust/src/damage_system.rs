@@ -1,39 +1,21 @@
 extern crate specs;
-use super::{CombatStats, Destructable, Map, Name, Player, Position, RunState, SufferDamage};
-use crate::gamelog::GameLog;
+use super::{
+    boss_system, morgue, spawner, Boss, CombatStats, Destructable, Equipped, InBackpack, Item,
+    LootTable, Map, Monster, Name, Player, Position, RandomNumberGenerator, Renderable, RunState,
+    TileType,
+};
+use crate::color::white;
+use crate::gamelog::{GameLog, LogBuilder};
+use crate::identification::item_display_name;
+use crate::statistics::{self, RunStats};
 use specs::prelude::*;
 
-pub struct DamageSystem {}
-
-impl<'a> System<'a> for DamageSystem {
-    type SystemData = (
-        WriteStorage<'a, CombatStats>,
-        WriteStorage<'a, SufferDamage>,
-        ReadStorage<'a, Position>,
-        WriteExpect<'a, Map>,
-        Entities<'a>,
-    );
-
-    fn run(&mut self, data: Self::SystemData) {
-        let (mut stats, mut damage, positions, mut map, entities) = data;
-
-        for (entity, mut stats, damage) in (&entities, &mut stats, &damage).join() {
-            stats.hp -= damage.amount.iter().sum::<i32>();
-
-            let pos = positions.get(entity);
-
-            // Bloodstains appear where an entity takes damage
-            if let Some(stain_pos) = pos {
-                let idx = map.xy_idx(stain_pos.x, stain_pos.y);
-                map.bloodstains.insert(idx);
-            }
-        }
-
-        damage.clear();
-    }
-}
 pub fn delete_the_dead(ecs: &mut World) {
     let mut dead: Vec<Entity> = Vec::new();
+    let mut corpses: Vec<(i32, i32, String)> = Vec::new();
+    let mut looters: Vec<(Entity, i32, i32)> = Vec::new();
+    let mut slain_bosses: Vec<String> = Vec::new();
+    let mut player_died = false;
     // Using a scope to make the borrow checker happy
     {
         let combat_stats = ecs.read_storage::<CombatStats>();
@@ -41,13 +23,16 @@ pub fn delete_the_dead(ecs: &mut World) {
         let players = ecs.read_storage::<Player>();
         let entities = ecs.entities();
         let destructables = ecs.read_storage::<Destructable>();
+        let monsters = ecs.read_storage::<Monster>();
+        let positions = ecs.read_storage::<Position>();
+        let bosses = ecs.read_storage::<Boss>();
+        let renderables = ecs.read_storage::<Renderable>();
         let mut log = ecs.write_resource::<GameLog>();
 
         for (entity, destructable) in (&entities, &destructables).join() {
             if destructable.broken {
                 dead.push(entity);
-                log.entries
-                    .push(format!("{} broke", names.get(entity).unwrap().name));
+                log.add(format!("{} broke", item_display_name(ecs, entity)));
             }
         }
 
@@ -57,20 +42,43 @@ pub fn delete_the_dead(ecs: &mut World) {
                 match player {
                     Some(_) => {
                         let mut runstate = ecs.write_resource::<RunState>();
+                        if *runstate != RunState::GameOver {
+                            player_died = true;
+                        }
                         *runstate = RunState::GameOver;
                     }
                     None => {
                         // TODO: not too stoked on this nested match expression
                         match names.get(entity) {
-                            Some(victim_name) => {
-                                log.entries.push(format!("{} was slain", victim_name.name))
-                            }
+                            Some(victim_name) => LogBuilder::new()
+                                .name(
+                                    &victim_name.name,
+                                    renderables.get(entity).map_or(white(), |r| r.fg),
+                                )
+                                .append(" was slain")
+                                .log(&mut log),
                             None => {
                                 // TODO: leaving this in for now but
                                 // entities with no name probably shouldn't happen
-                                log.entries.push(format!("unknown entity was slain"))
+                                log.add("unknown entity was slain".to_string())
                             }
                         }
+                        // Slain monsters leave their remains behind
+                        if let (Some(_monster), Some(pos), Some(name)) = (
+                            monsters.get(entity),
+                            positions.get(entity),
+                            names.get(entity),
+                        ) {
+                            corpses.push((pos.x, pos.y, name.name.clone()));
+                            *ecs.write_resource::<RunStats>()
+                                .kills
+                                .entry(name.name.clone())
+                                .or_insert(0) += 1;
+                            looters.push((entity, pos.x, pos.y));
+                        }
+                        if let (Some(_boss), Some(name)) = (bosses.get(entity), names.get(entity)) {
+                            slain_bosses.push(name.name.clone());
+                        }
                         dead.push(entity)
                     }
                 }
@@ -78,7 +86,104 @@ pub fn delete_the_dead(ecs: &mut World) {
         }
     }
 
+    if player_died {
+        statistics::record_run(ecs, false);
+        morgue::write_morgue(ecs);
+    }
+
+    for (victim, x, y) in looters {
+        drop_loot(ecs, victim, x, y);
+    }
     for victim in dead {
         ecs.delete_entity(victim).expect("Unable to delete");
     }
+    for (x, y, monster_name) in corpses {
+        spawner::corpse(ecs, x, y, &monster_name);
+    }
+    for boss_name in slain_bosses {
+        boss_system::open_sealed_gates(ecs, &boss_name);
+    }
+}
+
+/// Spreads out everything `victim` leaves behind: whatever it was carrying or wearing,
+/// and a roll on its loot table.
+fn drop_loot(ecs: &mut World, victim: Entity, x: i32, y: i32) {
+    let carried: Vec<Entity> = {
+        let entities = ecs.entities();
+        let backpack = ecs.read_storage::<InBackpack>();
+        let equipped = ecs.read_storage::<Equipped>();
+        entities
+            .join()
+            .filter(|item| {
+                backpack.get(*item).is_some_and(|b| b.owner == victim)
+                    || equipped.get(*item).is_some_and(|e| e.owner == victim)
+            })
+            .collect()
+    };
+    let loot = {
+        let loot_tables = ecs.read_storage::<LootTable>();
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        loot_tables
+            .get(victim)
+            .map(|loot| spawner::loot_table(&loot.table).roll(&mut rng))
+    };
+
+    let mut spots = drop_spots(ecs, x, y).into_iter().cycle();
+    for item in carried {
+        let (drop_x, drop_y) = spots.next().unwrap();
+        ecs.write_storage::<InBackpack>().remove(item);
+        ecs.write_storage::<Equipped>().remove(item);
+        ecs.write_storage::<Position>()
+            .insert(
+                item,
+                Position {
+                    x: drop_x,
+                    y: drop_y,
+                },
+            )
+            .expect("Unable to insert position");
+    }
+    if let Some(loot) = loot {
+        let (drop_x, drop_y) = spots.next().unwrap();
+        spawner::spawn_entity(ecs, drop_x, drop_y, &loot);
+    }
+}
+
+/// The death tile first, then the tiles around it that can be walked on and have nothing
+/// lying on them yet.
+fn drop_spots(ecs: &World, x: i32, y: i32) -> Vec<(i32, i32)> {
+    let map = ecs.fetch::<Map>();
+    let items = ecs.read_storage::<Item>();
+    let positions = ecs.read_storage::<Position>();
+    let occupied: Vec<(i32, i32)> = (&items, &positions)
+        .join()
+        .map(|(_item, pos)| (pos.x, pos.y))
+        .collect();
+
+    let mut spots = vec![(x, y)];
+    for (dx, dy) in [
+        (-1, -1),
+        (0, -1),
+        (1, -1),
+        (-1, 0),
+        (1, 0),
+        (-1, 1),
+        (0, 1),
+        (1, 1),
+    ]
+    .iter()
+    {
+        let (spot_x, spot_y) = (x + dx, y + dy);
+        if spot_x < 1 || spot_y < 1 || spot_x > map.width - 2 || spot_y > map.height - 2 {
+            continue;
+        }
+        let idx = map.xy_idx(spot_x, spot_y);
+        if map.tiles[idx] != TileType::Wall
+            && !map.blocked_tiles[idx]
+            && !occupied.contains(&(spot_x, spot_y))
+        {
+            spots.push((spot_x, spot_y));
+        }
+    }
+    spots
 }
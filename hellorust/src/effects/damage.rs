@@ -0,0 +1,76 @@
+use super::{EffectQueue, EffectSpawner, EffectType, Targets};
+use crate::experience::{gain_xp, xp_for_kill};
+use crate::statistics::{describe_killer, RunStats};
+use crate::{CombatStats, Confusion, Destructable, Map, Monster};
+use specs::prelude::*;
+
+pub fn inflict_damage(ecs: &mut World, damage: &EffectSpawner, target: Entity) {
+    if let EffectType::Damage { amount } = damage.effect_type {
+        let player_entity = *ecs.fetch::<Entity>();
+        let mut xp_earned = 0;
+        // Scoped so the storages are let go before experience is handed out
+        {
+            let mut stats = ecs.write_storage::<CombatStats>();
+            if let Some(stats) = stats.get_mut(target) {
+                let was_alive = stats.hp > 0;
+                stats.hp -= amount;
+                // Only the blow that kills a monster earns the player experience
+                if was_alive
+                    && stats.hp < 1
+                    && damage.creator == Some(player_entity)
+                    && ecs.read_storage::<Monster>().contains(target)
+                {
+                    xp_earned = xp_for_kill(stats);
+                }
+                let mut run_stats = ecs.write_resource::<RunStats>();
+                if target == player_entity {
+                    run_stats.damage_taken += amount;
+                    if stats.hp < 1 && run_stats.cause_of_death.is_none() {
+                        run_stats.cause_of_death = Some(describe_killer(ecs, damage.creator));
+                    }
+                } else if damage.creator == Some(player_entity) {
+                    run_stats.damage_dealt += amount;
+                }
+                // Bloodstains appear where an entity takes damage
+                ecs.write_resource::<EffectQueue>().add_effect(
+                    None,
+                    EffectType::Bloodstain,
+                    Targets::Single { target },
+                );
+            }
+
+            let mut destructables = ecs.write_storage::<Destructable>();
+            if let Some(destructable) = destructables.get_mut(target) {
+                destructable.broken = true;
+            }
+        }
+        if xp_earned > 0 {
+            gain_xp(ecs, player_entity, xp_earned);
+        }
+    }
+}
+
+pub fn heal_damage(ecs: &mut World, heal: &EffectSpawner, target: Entity) {
+    if let EffectType::Healing { amount } = heal.effect_type {
+        let mut stats = ecs.write_storage::<CombatStats>();
+        if let Some(stats) = stats.get_mut(target) {
+            stats.hp = i32::min(stats.max_hp, stats.hp + amount);
+        }
+    }
+}
+
+pub fn add_confusion(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
+    if let EffectType::Confusion { turns } = effect.effect_type {
+        // only things that can fight can be confused, not the potion on the floor
+        if ecs.read_storage::<CombatStats>().get(target).is_some() {
+            ecs.write_storage::<Confusion>()
+                .insert(target, Confusion { turns })
+                .expect("Unable to insert status");
+        }
+    }
+}
+
+pub fn bloodstain(ecs: &mut World, tile_idx: usize) {
+    let mut map = ecs.fetch_mut::<Map>();
+    map.bloodstains.insert(tile_idx);
+}
@@ -0,0 +1,305 @@
+// Items, traps, melee and spells don't change the world themselves: they put an
+// effect and the targets it should hit on the queue, and run_effects_queue applies
+// everything queued in one place.
+extern crate specs;
+use super::Map;
+use rltk::{Point, RGB};
+use specs::prelude::*;
+use std::collections::VecDeque;
+mod damage;
+mod movement;
+mod particles;
+mod targeting;
+mod triggers;
+pub use targeting::*;
+
+pub enum EffectType {
+    Damage {
+        amount: i32,
+    },
+    Healing {
+        amount: i32,
+    },
+    Confusion {
+        turns: i32,
+    },
+    TeleportTo {
+        x: i32,
+        y: i32,
+    },
+    Particle {
+        glyph: u16,
+        fg: RGB,
+        bg: RGB,
+        lifespan: f32,
+    },
+    Bloodstain,
+    ItemUse {
+        item: Entity,
+    },
+    TriggerFire {
+        trigger: Entity,
+    },
+}
+
+#[derive(Clone)]
+pub enum Targets {
+    Single { target: Entity },
+    Tile { tile_idx: usize },
+    Tiles { tiles: Vec<usize> },
+    Area { target: Point, radius: i32 },
+}
+
+pub struct EffectSpawner {
+    pub creator: Option<Entity>,
+    pub effect_type: EffectType,
+    pub targets: Targets,
+}
+
+pub struct EffectQueue {
+    requests: VecDeque<EffectSpawner>,
+}
+
+impl EffectQueue {
+    pub fn new() -> EffectQueue {
+        EffectQueue {
+            requests: VecDeque::new(),
+        }
+    }
+
+    pub fn add_effect(
+        &mut self,
+        creator: Option<Entity>,
+        effect_type: EffectType,
+        targets: Targets,
+    ) {
+        self.requests.push_back(EffectSpawner {
+            creator,
+            effect_type,
+            targets,
+        });
+    }
+}
+
+/// Pops effects off the queue until it is empty. Effects may enqueue
+/// further effects (damage adds a bloodstain, an item use adds damage),
+/// those are handled in the same call.
+pub fn run_effects_queue(ecs: &mut World) {
+    loop {
+        let effect: Option<EffectSpawner> =
+            ecs.write_resource::<EffectQueue>().requests.pop_front();
+        match effect {
+            Some(effect) => target_applicator(ecs, &effect),
+            None => break,
+        }
+    }
+}
+
+fn target_applicator(ecs: &mut World, effect: &EffectSpawner) {
+    match &effect.effect_type {
+        EffectType::ItemUse { item } => {
+            triggers::item_trigger(effect.creator, *item, &effect.targets, ecs)
+        }
+        EffectType::TriggerFire { trigger } => {
+            triggers::trigger(effect.creator, *trigger, &effect.targets, ecs)
+        }
+        _ => match &effect.targets {
+            Targets::Single { target } => affect_entity(ecs, effect, *target),
+            Targets::Tile { tile_idx } => affect_tile(ecs, effect, *tile_idx),
+            Targets::Tiles { tiles } => {
+                for tile_idx in tiles.iter() {
+                    affect_tile(ecs, effect, *tile_idx);
+                }
+            }
+            Targets::Area { target, radius } => {
+                let tiles = aoe_tiles(&ecs.fetch::<Map>(), *target, *radius);
+                for tile_idx in tiles.iter() {
+                    affect_tile(ecs, effect, *tile_idx);
+                }
+            }
+        },
+    }
+}
+
+fn tile_effect_hits_entities(effect: &EffectType) -> bool {
+    matches!(
+        effect,
+        EffectType::Damage { .. }
+            | EffectType::Healing { .. }
+            | EffectType::Confusion { .. }
+            | EffectType::TeleportTo { .. }
+    )
+}
+
+fn affect_tile(ecs: &mut World, effect: &EffectSpawner, tile_idx: usize) {
+    if tile_effect_hits_entities(&effect.effect_type) {
+        let content = ecs.fetch::<Map>().tile_content[tile_idx].clone();
+        for entity in content.iter() {
+            affect_entity(ecs, effect, *entity);
+        }
+    }
+
+    match &effect.effect_type {
+        EffectType::Bloodstain => damage::bloodstain(ecs, tile_idx),
+        EffectType::Particle { .. } => particles::particle_to_tile(ecs, tile_idx, effect),
+        _ => {}
+    }
+}
+
+fn affect_entity(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
+    match &effect.effect_type {
+        EffectType::Damage { .. } => damage::inflict_damage(ecs, effect, target),
+        EffectType::Healing { .. } => damage::heal_damage(ecs, effect, target),
+        EffectType::Confusion { .. } => damage::add_confusion(ecs, effect, target),
+        EffectType::TeleportTo { .. } => movement::apply_teleport(ecs, effect, target),
+        EffectType::Bloodstain => {
+            if let Some(tile_idx) = entity_position(ecs, target) {
+                damage::bloodstain(ecs, tile_idx)
+            }
+        }
+        EffectType::Particle { .. } => {
+            if let Some(tile_idx) = entity_position(ecs, target) {
+                particles::particle_to_tile(ecs, tile_idx, effect)
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::statistics::RunStats;
+    use crate::{CombatStats, Confusion, Destructable, Experience, Monster, Position};
+
+    fn test_world() -> World {
+        let mut ecs = World::new();
+        ecs.register::<CombatStats>();
+        ecs.register::<Confusion>();
+        ecs.register::<Destructable>();
+        ecs.register::<Experience>();
+        ecs.register::<Monster>();
+        ecs.register::<Position>();
+        ecs.insert(Map::new(1));
+        ecs.insert(EffectQueue::new());
+        ecs.insert(RunStats::new());
+        let player = ecs
+            .create_entity()
+            .with(Experience { level: 1, xp: 0 })
+            .build();
+        ecs.insert(player);
+        ecs
+    }
+
+    fn spawn_fighter(ecs: &mut World, x: i32, y: i32) -> Entity {
+        let fighter = ecs
+            .create_entity()
+            .with(Position { x, y })
+            .with(CombatStats {
+                max_hp: 10,
+                hp: 10,
+                defense: 0,
+                strength: 1,
+            })
+            .build();
+        let mut map = ecs.fetch_mut::<Map>();
+        let idx = map.xy_idx(x, y);
+        map.tile_content[idx].push(fighter);
+        fighter
+    }
+
+    fn hp(ecs: &World, entity: Entity) -> i32 {
+        ecs.read_storage::<CombatStats>().get(entity).unwrap().hp
+    }
+
+    #[test]
+    fn effects_are_applied_in_order_and_the_queue_is_emptied() {
+        let mut ecs = test_world();
+        let target = spawn_fighter(&mut ecs, 5, 5);
+        {
+            let mut queue = ecs.write_resource::<EffectQueue>();
+            queue.add_effect(
+                None,
+                EffectType::Damage { amount: 8 },
+                Targets::Single { target },
+            );
+            queue.add_effect(
+                None,
+                EffectType::Healing { amount: 5 },
+                Targets::Single { target },
+            );
+        }
+        run_effects_queue(&mut ecs);
+        // 10 - 8 + 5; healing first would have been capped at 10, leaving 2
+        assert_eq!(hp(&ecs, target), 7);
+        assert!(ecs.fetch::<EffectQueue>().requests.is_empty());
+    }
+
+    #[test]
+    fn effects_queued_by_effects_run_in_the_same_call() {
+        let mut ecs = test_world();
+        let target = spawn_fighter(&mut ecs, 5, 5);
+        ecs.write_resource::<EffectQueue>().add_effect(
+            None,
+            EffectType::Damage { amount: 1 },
+            Targets::Single { target },
+        );
+        run_effects_queue(&mut ecs);
+        let map = ecs.fetch::<Map>();
+        assert!(map.bloodstains.contains(&map.xy_idx(5, 5)));
+    }
+
+    #[test]
+    fn tile_effects_hit_everything_on_the_tile() {
+        let mut ecs = test_world();
+        let first = spawn_fighter(&mut ecs, 5, 5);
+        let second = spawn_fighter(&mut ecs, 5, 5);
+        let bystander = spawn_fighter(&mut ecs, 6, 5);
+        let tile_idx = ecs.fetch::<Map>().xy_idx(5, 5);
+        ecs.write_resource::<EffectQueue>().add_effect(
+            None,
+            EffectType::Confusion { turns: 3 },
+            Targets::Tile { tile_idx },
+        );
+        run_effects_queue(&mut ecs);
+        let confusion = ecs.read_storage::<Confusion>();
+        assert!(confusion.contains(first));
+        assert!(confusion.contains(second));
+        assert!(!confusion.contains(bystander));
+    }
+
+    #[test]
+    fn damage_from_the_player_counts_towards_damage_dealt() {
+        let mut ecs = test_world();
+        let player = *ecs.fetch::<Entity>();
+        let target = spawn_fighter(&mut ecs, 5, 5);
+        ecs.write_resource::<EffectQueue>().add_effect(
+            Some(player),
+            EffectType::Damage { amount: 4 },
+            Targets::Single { target },
+        );
+        run_effects_queue(&mut ecs);
+        assert_eq!(hp(&ecs, target), 6);
+        assert_eq!(ecs.fetch::<RunStats>().damage_dealt, 4);
+    }
+
+    #[test]
+    fn the_killing_blow_earns_the_player_experience() {
+        let mut ecs = test_world();
+        let player = *ecs.fetch::<Entity>();
+        let monster = spawn_fighter(&mut ecs, 5, 5);
+        ecs.write_storage::<Monster>()
+            .insert(monster, Monster {})
+            .expect("Unable to insert monster");
+        for _ in 0..2 {
+            ecs.write_resource::<EffectQueue>().add_effect(
+                Some(player),
+                EffectType::Damage { amount: 10 },
+                Targets::Single { target: monster },
+            );
+        }
+        run_effects_queue(&mut ecs);
+        // the second hit lands on a corpse and earns nothing more
+        assert_eq!(ecs.read_storage::<Experience>().get(player).unwrap().xp, 10);
+    }
+}
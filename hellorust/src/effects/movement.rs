@@ -0,0 +1,30 @@
+use super::{EffectSpawner, EffectType};
+use crate::{EntityMoved, Position, Viewshed};
+use rltk::Point;
+use specs::prelude::*;
+
+pub fn apply_teleport(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
+    if let EffectType::TeleportTo { x, y } = effect.effect_type {
+        let mut positions = ecs.write_storage::<Position>();
+        if let Some(pos) = positions.get_mut(target) {
+            pos.x = x;
+            pos.y = y;
+        } else {
+            return;
+        }
+
+        if let Some(viewshed) = ecs.write_storage::<Viewshed>().get_mut(target) {
+            viewshed.dirty = true;
+        }
+        ecs.write_storage::<EntityMoved>()
+            .insert(target, EntityMoved {})
+            .expect("Unable to insert marker");
+
+        // keep the player position resource in sync
+        let player_entity = *ecs.fetch::<Entity>();
+        if target == player_entity {
+            let mut player_pos = ecs.write_resource::<Point>();
+            *player_pos = Point::new(x, y);
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use super::{EffectSpawner, EffectType};
+use crate::{Map, ParticleBuilder};
+use specs::prelude::*;
+
+pub fn particle_to_tile(ecs: &mut World, tile_idx: usize, effect: &EffectSpawner) {
+    if let EffectType::Particle {
+        glyph,
+        fg,
+        bg,
+        lifespan,
+    } = effect.effect_type
+    {
+        let map = ecs.fetch::<Map>();
+        let mut particle_builder = ecs.fetch_mut::<ParticleBuilder>();
+        particle_builder.request(
+            tile_idx as i32 % map.width,
+            tile_idx as i32 / map.width,
+            fg,
+            bg,
+            glyph,
+            lifespan,
+        );
+    }
+}
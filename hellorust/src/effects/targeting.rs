@@ -0,0 +1,36 @@
+use super::Targets;
+use crate::{Map, Position};
+use rltk::Point;
+use specs::prelude::*;
+
+/// Map index of the tile an entity is standing on, if it is on the map at all.
+pub fn entity_position(ecs: &World, target: Entity) -> Option<usize> {
+    let positions = ecs.read_storage::<Position>();
+    let map = ecs.fetch::<Map>();
+    positions.get(target).map(|pos| map.xy_idx(pos.x, pos.y))
+}
+
+/// Tiles caught in a blast of `radius` around `target`. Walls block the blast.
+pub fn aoe_tiles(map: &Map, target: Point, radius: i32) -> Vec<usize> {
+    let mut blast_tiles = rltk::field_of_view(target, radius, map);
+    // remove out of bounds tiles
+    blast_tiles.retain(|p| p.x > 0 && p.x < map.width - 1 && p.y > 0 && p.y < map.height - 1);
+    blast_tiles.iter().map(|p| map.xy_idx(p.x, p.y)).collect()
+}
+
+/// Every entity that an effect with these targets would touch.
+pub fn entities_in_targets(ecs: &World, targets: &Targets) -> Vec<Entity> {
+    let map = ecs.fetch::<Map>();
+    match targets {
+        Targets::Single { target } => vec![*target],
+        Targets::Tile { tile_idx } => map.tile_content[*tile_idx].clone(),
+        Targets::Tiles { tiles } => tiles
+            .iter()
+            .flat_map(|idx| map.tile_content[*idx].iter().copied())
+            .collect(),
+        Targets::Area { target, radius } => aoe_tiles(&map, *target, *radius)
+            .iter()
+            .flat_map(|idx| map.tile_content[*idx].iter().copied())
+            .collect(),
+    }
+}
@@ -0,0 +1,398 @@
+use super::{aoe_tiles, entities_in_targets, EffectQueue, EffectType, Targets};
+use crate::color::*;
+use crate::companion_ai_system::summon_spot;
+use crate::corpse_system::CORPSE_ROTS_AFTER;
+use crate::gamelog::{name_color, GameLog, LogBuilder};
+use crate::identification::identify_item;
+use crate::statistics::RunStats;
+use crate::{
+    spawner, Charges, Companion, Confusion, Consumable, Corpse, HungerClock, HungerState,
+    InflictsDamage, KnownSpell, MagicMapper, ManaPool, Map, Name, ProvidesFood, ProvidesHealing,
+    RandomTeleport, RunState, SingleActivation, SummonsCompanion, TeachesSpell, TileType,
+};
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+
+const WAND_EXPLOSION_DAMAGE: i32 = 6;
+const CORPSE_POISON_DAMAGE: i32 = 4;
+
+/// An item was used by `creator` on `targets`.
+pub fn item_trigger(creator: Option<Entity>, item: Entity, targets: &Targets, ecs: &mut World) {
+    // Using an unknown magic item tells you what it was
+    if creator == Some(*ecs.fetch::<Entity>()) {
+        identify_item(ecs, item);
+    }
+
+    // Zapping a wand that has nothing left in it is a bad idea
+    let is_empty_wand = ecs
+        .read_storage::<Charges>()
+        .get(item)
+        .is_some_and(|c| c.charges < 1);
+    if is_empty_wand {
+        explode_wand(creator, item, ecs);
+        return;
+    }
+
+    let did_something = event_trigger(creator, item, targets, ecs);
+
+    // spells are paid for in mana
+    if did_something {
+        let mana_cost = ecs
+            .read_storage::<KnownSpell>()
+            .get(item)
+            .map(|spell| spell.mana_cost);
+        if let (Some(mana_cost), Some(creator)) = (mana_cost, creator) {
+            if let Some(pool) = ecs.write_storage::<ManaPool>().get_mut(creator) {
+                pool.mana = i32::max(0, pool.mana - mana_cost);
+            }
+        }
+    }
+
+    // spells don't count as items
+    if did_something
+        && creator == Some(*ecs.fetch::<Entity>())
+        && ecs.read_storage::<KnownSpell>().get(item).is_none()
+    {
+        ecs.write_resource::<RunStats>().items_used += 1;
+    }
+
+    // delete consumed items
+    if did_something && ecs.read_storage::<Consumable>().get(item).is_some() {
+        ecs.entities().delete(item).expect("Delete failed");
+    }
+
+    // wands use up a charge instead
+    if did_something {
+        if let Some(charges) = ecs.write_storage::<Charges>().get_mut(item) {
+            charges.charges -= 1;
+            if charges.charges < 1 && creator == Some(*ecs.fetch::<Entity>()) {
+                let item_name = ecs.read_storage::<Name>().get(item).unwrap().name.clone();
+                ecs.fetch_mut::<GameLog>()
+                    .add(format!("The {} is out of charges.", item_name));
+            }
+        }
+    }
+}
+
+/// An empty wand blows up in the hands of whoever tries to use it.
+fn explode_wand(creator: Option<Entity>, item: Entity, ecs: &mut World) {
+    let item_name = ecs.read_storage::<Name>().get(item).unwrap().name.clone();
+    if let Some(creator) = creator {
+        if creator == *ecs.fetch::<Entity>() {
+            ecs.fetch_mut::<GameLog>().add(format!(
+                "The {} explodes in your hands for {} damage!",
+                item_name, WAND_EXPLOSION_DAMAGE
+            ));
+        }
+        let mut effects = ecs.write_resource::<EffectQueue>();
+        effects.add_effect(
+            None,
+            EffectType::Damage {
+                amount: WAND_EXPLOSION_DAMAGE,
+            },
+            Targets::Single { target: creator },
+        );
+        effects.add_effect(
+            None,
+            EffectType::Particle {
+                glyph: rltk::to_cp437('*'),
+                fg: orange(),
+                bg: black(),
+                lifespan: 300.0,
+            },
+            Targets::Single { target: creator },
+        );
+    }
+    ecs.entities().delete(item).expect("Delete failed");
+}
+
+/// A trap (or any other `EntryTrigger`) went off on `targets`.
+pub fn trigger(creator: Option<Entity>, trigger: Entity, targets: &Targets, ecs: &mut World) {
+    let did_something = event_trigger(creator, trigger, targets, ecs);
+
+    if did_something
+        && ecs
+            .read_storage::<SingleActivation>()
+            .get(trigger)
+            .is_some()
+    {
+        ecs.entities()
+            .delete(trigger)
+            .expect("Unable to delete trap");
+    }
+}
+
+/// Shared by items and traps: looks at the components on `entity` and
+/// enqueues the matching effects. Returns whether anything happened.
+fn event_trigger(
+    creator: Option<Entity>,
+    entity: Entity,
+    targets: &Targets,
+    ecs: &mut World,
+) -> bool {
+    let mut did_something = false;
+    let player_entity = *ecs.fetch::<Entity>();
+    let by_player = creator == Some(player_entity);
+    let entity_name = ecs
+        .read_storage::<Name>()
+        .get(entity)
+        .map_or("unknown item".to_string(), |n| n.name.clone());
+    let mut effects = Vec::new();
+    let mut messages = Vec::new();
+
+    // Blasts light up every tile they touch
+    if let Targets::Area { target, radius } = targets {
+        let tiles = aoe_tiles(&ecs.fetch::<Map>(), *target, *radius);
+        effects.push((
+            EffectType::Particle {
+                glyph: rltk::to_cp437('░'),
+                fg: orange(),
+                bg: black(),
+                lifespan: 250.0,
+            },
+            Targets::Tiles { tiles },
+        ));
+    }
+
+    // Edibles reset hungerclock
+    let nutrition = ecs
+        .read_storage::<ProvidesFood>()
+        .get(entity)
+        .map(|food| food.nutrition);
+    if let Some(nutrition) = nutrition {
+        let poisoned = corpse_is_poisonous(ecs, entity);
+        let mut hunger_clocks = ecs.write_storage::<HungerClock>();
+        for target in entities_in_targets(ecs, targets).iter() {
+            if let Some(hc) = hunger_clocks.get_mut(*target) {
+                did_something = true;
+                if poisoned {
+                    if by_player {
+                        messages.push(LogBuilder::new().append(format!(
+                            "You eat the {}. It was bad, you feel sick!",
+                            entity_name
+                        )));
+                    }
+                    effects.push((
+                        EffectType::Damage {
+                            amount: CORPSE_POISON_DAMAGE,
+                        },
+                        Targets::Single { target: *target },
+                    ));
+                    continue;
+                }
+                hc.state = HungerState::WellFed;
+                hc.duration = nutrition;
+                if by_player {
+                    messages
+                        .push(LogBuilder::new().append(format!("You eat the {}.", entity_name)));
+                }
+            }
+        }
+    }
+
+    // If its a Magic Mapper
+    if ecs.read_storage::<MagicMapper>().get(entity).is_some() {
+        did_something = true;
+        messages.push(LogBuilder::new().append("All is revealed to you!".to_string()));
+        *ecs.write_resource::<RunState>() = RunState::MagicMapReveal { row: 0 };
+    }
+
+    // Healing
+    if let Some(healer) = ecs.read_storage::<ProvidesHealing>().get(entity) {
+        did_something = true;
+        if by_player {
+            messages.push(
+                LogBuilder::new()
+                    .append(format!("You use the {}, healing ", entity_name))
+                    .color(healer.heal_amount, green())
+                    .append(" hp."),
+            );
+        }
+        effects.push((
+            EffectType::Healing {
+                amount: healer.heal_amount,
+            },
+            targets.clone(),
+        ));
+        effects.push((
+            EffectType::Particle {
+                glyph: rltk::to_cp437('♥'),
+                fg: green(),
+                bg: black(),
+                lifespan: 200.0,
+            },
+            targets.clone(),
+        ));
+    }
+
+    // Damaging
+    if let Some(damage) = ecs.read_storage::<InflictsDamage>().get(entity) {
+        let names = ecs.read_storage::<Name>();
+        for target in entities_in_targets(ecs, targets).iter() {
+            did_something = true;
+            if by_player && is_companion(ecs, *target) {
+                continue;
+            }
+            if by_player {
+                if let Some(target_name) = names.get(*target) {
+                    messages.push(
+                        LogBuilder::new()
+                            .append(format!("You use {} on ", entity_name))
+                            .name(&target_name.name, name_color(ecs, *target))
+                            .append(", inflicting ")
+                            .damage(damage.damage)
+                            .append(" damage."),
+                    );
+                }
+            }
+            effects.push((
+                EffectType::Damage {
+                    amount: damage.damage,
+                },
+                Targets::Single { target: *target },
+            ));
+            effects.push((
+                EffectType::Particle {
+                    glyph: rltk::to_cp437('‼'),
+                    fg: red(),
+                    bg: black(),
+                    lifespan: 200.0,
+                },
+                Targets::Single { target: *target },
+            ));
+        }
+    }
+
+    // "Can it pass along confusion?"
+    if let Some(confusion) = ecs.read_storage::<Confusion>().get(entity) {
+        let names = ecs.read_storage::<Name>();
+        for target in entities_in_targets(ecs, targets).iter() {
+            did_something = true;
+            if by_player && is_companion(ecs, *target) {
+                continue;
+            }
+            if by_player {
+                if let Some(mob_name) = names.get(*target) {
+                    messages.push(LogBuilder::new().append(format!(
+                        "You use {} on {}, confusing them.",
+                        entity_name, mob_name.name
+                    )));
+                }
+            }
+            effects.push((
+                EffectType::Confusion {
+                    turns: confusion.turns,
+                },
+                Targets::Single { target: *target },
+            ));
+            effects.push((
+                EffectType::Particle {
+                    glyph: rltk::to_cp437('?'),
+                    fg: magenta(),
+                    bg: black(),
+                    lifespan: 1000.0,
+                },
+                Targets::Single { target: *target },
+            ));
+        }
+    }
+
+    // Tomes teach their reader a spell
+    let teaches_spell = ecs
+        .read_storage::<TeachesSpell>()
+        .get(entity)
+        .map(|teaches| teaches.spell.clone());
+    if let (Some(spell), Some(creator)) = (teaches_spell, creator) {
+        did_something = true;
+        if spawner::knows_spell(ecs, creator, &spell) {
+            if by_player {
+                messages.push(LogBuilder::new().append(format!("You already know {}.", spell)));
+            }
+        } else {
+            spawner::learn_spell(ecs, creator, &spell);
+            if by_player {
+                messages.push(LogBuilder::new().append(format!("You learn to cast {}!", spell)));
+            }
+        }
+    }
+
+    // Summoning brings a new friend
+    if ecs.read_storage::<SummonsCompanion>().get(entity).is_some() {
+        if let Some(creator) = creator {
+            if let Some(spot) = summon_spot(ecs, creator) {
+                did_something = true;
+                spawner::companion(ecs, spot.x, spot.y, rltk::to_cp437('w'), "Spirit Wolf");
+                if by_player {
+                    messages.push(
+                        LogBuilder::new().append("A spirit wolf answers your call!".to_string()),
+                    );
+                }
+            }
+        }
+    }
+
+    // Teleport traps throw their victim to a random spot on the level
+    if ecs.read_storage::<RandomTeleport>().get(entity).is_some() {
+        for target in entities_in_targets(ecs, targets).iter() {
+            if let Some((x, y)) = random_floor_tile(ecs) {
+                did_something = true;
+                if *target == player_entity {
+                    messages
+                        .push(LogBuilder::new().append("The world spins around you!".to_string()));
+                }
+                effects.push((
+                    EffectType::TeleportTo { x, y },
+                    Targets::Single { target: *target },
+                ));
+            }
+        }
+    }
+
+    {
+        let mut log = ecs.fetch_mut::<GameLog>();
+        for message in messages {
+            message.log(&mut log);
+        }
+    }
+    let mut queue = ecs.write_resource::<EffectQueue>();
+    for (effect_type, effect_targets) in effects {
+        queue.add_effect(creator, effect_type, effect_targets);
+    }
+
+    did_something
+}
+
+/// Rolls whether eating `entity` makes you sick. Rotten corpses are much more likely to.
+fn corpse_is_poisonous(ecs: &World, entity: Entity) -> bool {
+    match ecs.read_storage::<Corpse>().get(entity) {
+        None => false,
+        Some(corpse) => {
+            let mut chance = corpse.poison_chance;
+            if corpse.age >= CORPSE_ROTS_AFTER {
+                chance += 50;
+            }
+            ecs.write_resource::<RandomNumberGenerator>()
+                .roll_dice(1, 100)
+                <= chance
+        }
+    }
+}
+
+/// The player's own blasts don't hurt their friends.
+fn is_companion(ecs: &World, entity: Entity) -> bool {
+    ecs.read_storage::<Companion>().get(entity).is_some()
+}
+
+fn random_floor_tile(ecs: &World) -> Option<(i32, i32)> {
+    let map = ecs.fetch::<Map>();
+    let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+    for _try in 0..100 {
+        let x = rng.roll_dice(1, map.width - 2);
+        let y = rng.roll_dice(1, map.height - 2);
+        let idx = map.xy_idx(x, y);
+        if map.tiles[idx] == TileType::Floor && !map.blocked_tiles[idx] {
+            return Some((x, y));
+        }
+    }
+    None
+}
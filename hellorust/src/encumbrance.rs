@@ -0,0 +1,60 @@
+use super::{CombatStats, Equipped, InBackpack, Item};
+use specs::prelude::*;
+
+/// How many pounds one point of strength lets you carry around unhindered.
+const POUNDS_PER_STRENGTH: f32 = 10.0;
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum Encumbrance {
+    Unburdened,
+    Burdened,
+    Overloaded,
+}
+
+/// Total weight of everything `owner` has in their backpack or equipped.
+pub fn carried_weight(ecs: &World, owner: Entity) -> f32 {
+    let items = ecs.read_storage::<Item>();
+    let backpack = ecs.read_storage::<InBackpack>();
+    let equipped = ecs.read_storage::<Equipped>();
+
+    let in_pack: f32 = (&items, &backpack)
+        .join()
+        .filter(|(_item, pack)| pack.owner == owner)
+        .map(|(item, _pack)| item.weight)
+        .sum();
+    let worn: f32 = (&items, &equipped)
+        .join()
+        .filter(|(_item, equipped)| equipped.owner == owner)
+        .map(|(item, _equipped)| item.weight)
+        .sum();
+    in_pack + worn
+}
+
+pub fn carry_capacity(stats: &CombatStats) -> f32 {
+    stats.strength as f32 * POUNDS_PER_STRENGTH
+}
+
+pub fn encumbrance(ecs: &World, entity: Entity) -> Encumbrance {
+    let capacity = match ecs.read_storage::<CombatStats>().get(entity) {
+        Some(stats) => carry_capacity(stats),
+        None => return Encumbrance::Unburdened,
+    };
+    let weight = carried_weight(ecs, entity);
+    if weight > capacity * 1.5 {
+        Encumbrance::Overloaded
+    } else if weight > capacity {
+        Encumbrance::Burdened
+    } else {
+        Encumbrance::Unburdened
+    }
+}
+
+/// "Speed" penalty: the more you lug around, the more likely it is that
+/// the monsters get to act again before you do.
+pub fn extra_turn_chance(encumbrance: Encumbrance) -> i32 {
+    match encumbrance {
+        Encumbrance::Unburdened => 0,
+        Encumbrance::Burdened => 33,
+        Encumbrance::Overloaded => 66,
+    }
+}
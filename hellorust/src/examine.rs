@@ -0,0 +1,174 @@
+// What the player learns by taking a closer look at a tile, for the keyboard look mode.
+use super::{
+    corpse_system::CORPSE_ROTS_AFTER,
+    identification::{is_unidentified, item_display_name},
+    Boss, Bystander, Charges, CombatStats, Companion, Corpse, CursedItem, DefenseBonus, Equippable,
+    Equipped, Hidden, Item, Map, MeleePowerBonus, Monster, Name, Position, ProvidesFood,
+    ProvidesHealing, RangedAttacker, TileType, Vendor,
+};
+use specs::prelude::*;
+
+/// One thing on the examined tile: a headline and a few lines of detail.
+pub struct Description {
+    pub title: String,
+    pub details: Vec<String>,
+}
+
+/// Everything the player can tell about the tile at `x`, `y`, the tile itself last.
+pub fn describe_tile(ecs: &World, x: i32, y: i32) -> Vec<Description> {
+    let map = ecs.fetch::<Map>();
+    let idx = map.xy_idx(x, y);
+    let mut descriptions = Vec::new();
+    if !map.revealed_tiles[idx] {
+        descriptions.push(Description {
+            title: "Unexplored".to_string(),
+            details: vec!["You haven't been there yet.".to_string()],
+        });
+        return descriptions;
+    }
+
+    if map.visible_tiles[idx] {
+        let entities = ecs.entities();
+        let positions = ecs.read_storage::<Position>();
+        let hidden = ecs.read_storage::<Hidden>();
+        for (entity, pos, _hidden) in (&entities, &positions, !&hidden).join() {
+            if pos.x == x && pos.y == y {
+                descriptions.push(describe_entity(ecs, entity));
+            }
+        }
+    }
+
+    let mut details = Vec::new();
+    if !map.visible_tiles[idx] {
+        details.push("You remember it like this, but can't see it now.".to_string());
+    }
+    if map.bloodstains.contains(&idx) {
+        details.push("There is blood on the ground.".to_string());
+    }
+    let (title, info) = tile_info(map.tiles[idx]);
+    details.insert(0, info.to_string());
+    descriptions.push(Description {
+        title: title.to_string(),
+        details,
+    });
+    descriptions
+}
+
+fn tile_info(tile: TileType) -> (&'static str, &'static str) {
+    match tile {
+        TileType::Wall => ("Wall", "Solid rock."),
+        TileType::Floor => ("Floor", "Bare stone floor."),
+        TileType::DownStairs => ("Stairs down", "They lead deeper into the dungeon."),
+        TileType::UpStairs => ("Stairs up", "They lead back towards the surface."),
+        TileType::Road => ("Road", "A well-trodden road."),
+        TileType::Grass => ("Grass", "Soft grass."),
+        TileType::Door => ("Door", "You can walk through, but not see through it."),
+        TileType::SealedGate => ("Sealed gate", "It won't open while the boss lives."),
+    }
+}
+
+pub fn describe_entity(ecs: &World, entity: Entity) -> Description {
+    let player_entity = *ecs.fetch::<Entity>();
+    let mut details = Vec::new();
+
+    let title = if ecs.read_storage::<Item>().contains(entity) {
+        describe_item(ecs, entity, &mut details);
+        item_display_name(ecs, entity)
+    } else {
+        ecs.read_storage::<Name>()
+            .get(entity)
+            .map_or("Something".to_string(), |name| name.name.clone())
+    };
+
+    if entity == player_entity {
+        details.push("That's you.".to_string());
+    }
+    if ecs.read_storage::<Boss>().contains(entity) {
+        details.push("The master of this level.".to_string());
+    }
+    if ecs.read_storage::<Monster>().contains(entity) {
+        details.push("Hostile.".to_string());
+    }
+    if ecs.read_storage::<Companion>().contains(entity) {
+        details.push("Your loyal companion.".to_string());
+    }
+    if ecs.read_storage::<Vendor>().contains(entity) {
+        details.push("A merchant. Bump into them to trade.".to_string());
+    }
+    if ecs.read_storage::<Bystander>().contains(entity) {
+        details.push("A peaceful townsperson.".to_string());
+    }
+    if ecs.read_storage::<RangedAttacker>().contains(entity) {
+        details.push("Attacks from a distance.".to_string());
+    }
+    if let Some(stats) = ecs.read_storage::<CombatStats>().get(entity) {
+        details.push(format!("{}.", health_status(stats)));
+    }
+    if let Some(corpse) = ecs.read_storage::<Corpse>().get(entity) {
+        if corpse.age >= CORPSE_ROTS_AFTER {
+            details.push("It has gone rotten.".to_string());
+        } else {
+            details.push("Still fresh enough to eat, probably.".to_string());
+        }
+    }
+
+    Description { title, details }
+}
+
+/// How hurt someone looks, without giving away exact numbers.
+pub fn health_status(stats: &CombatStats) -> &'static str {
+    let percent = stats.hp * 100 / i32::max(1, stats.max_hp);
+    match percent {
+        100..=i32::MAX => "Unhurt",
+        75..=99 => "Lightly wounded",
+        50..=74 => "Wounded",
+        25..=49 => "Badly wounded",
+        _ => "Almost dead",
+    }
+}
+
+fn describe_item(ecs: &World, item: Entity, details: &mut Vec<String>) {
+    if let Some(stats) = ecs.read_storage::<Item>().get(item) {
+        details.push(format!(
+            "Weighs {:.1} lbs, worth {} gold.",
+            stats.weight, stats.value
+        ));
+    }
+    if let Some(equippable) = ecs.read_storage::<Equippable>().get(item) {
+        if equippable.two_handed {
+            details.push("Needs both hands.".to_string());
+        }
+    }
+    // Gear only shows how good it is once it's on you. Cursed copies share their
+    // real counterpart's name, so telling the bonus of either would give the curse away.
+    let worn = ecs.read_storage::<Equipped>().contains(item);
+    if worn {
+        if let Some(bonus) = ecs.read_storage::<MeleePowerBonus>().get(item) {
+            details.push(format!("{:+} melee power.", bonus.power));
+        }
+        if let Some(bonus) = ecs.read_storage::<DefenseBonus>().get(item) {
+            details.push(format!("{:+} defense.", bonus.defense));
+        }
+        if ecs.read_storage::<CursedItem>().contains(item) {
+            details.push("Cursed!".to_string());
+        }
+    } else if ecs.read_storage::<Equippable>().contains(item) {
+        details.push("Wear it to find out how good it is.".to_string());
+    }
+    if is_unidentified(ecs, item) {
+        details.push("You don't know what it does yet.".to_string());
+        return;
+    }
+    if let Some(charges) = ecs.read_storage::<Charges>().get(item) {
+        details.push(format!(
+            "{} of {} charges left.",
+            charges.charges, charges.max_charges
+        ));
+    }
+    if let Some(healing) = ecs.read_storage::<ProvidesHealing>().get(item) {
+        details.push(format!("Heals {} hp.", healing.heal_amount));
+    }
+    if ecs.read_storage::<ProvidesFood>().contains(item) {
+        details.push("Edible.".to_string());
+    }
+}
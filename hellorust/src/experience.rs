@@ -0,0 +1,80 @@
+// Killing monsters earns the player experience; enough of it and they go up a level,
+// which makes them tougher and teaches them the next spell they don't know yet.
+use super::{
+    color::*,
+    gamelog::{GameLog, LogBuilder},
+    spawner, CombatStats, Experience, ManaPool,
+};
+use specs::prelude::*;
+
+const HP_PER_LEVEL: i32 = 5;
+const MANA_PER_LEVEL: i32 = 2;
+
+/// The spell taught on reaching each level, if the player doesn't know it already.
+const LEVEL_SPELLS: [(i32, &str); 4] = [
+    (2, "Magic Missile"),
+    (3, "Mending"),
+    (4, "Confusion"),
+    (5, "Fireball"),
+];
+
+/// How much experience it takes to get from `level` to the next one.
+pub fn xp_to_next_level(level: i32) -> i32 {
+    level * 50
+}
+
+/// Experience for killing something this tough.
+pub fn xp_for_kill(victim: &CombatStats) -> i32 {
+    victim.max_hp
+}
+
+pub fn level_spell(level: i32) -> Option<&'static str> {
+    LEVEL_SPELLS
+        .iter()
+        .find(|(spell_level, _spell)| *spell_level == level)
+        .map(|(_level, spell)| *spell)
+}
+
+/// Adds experience, going up as many levels as it pays for.
+pub fn gain_xp(ecs: &mut World, entity: Entity, amount: i32) {
+    let mut new_levels = Vec::new();
+    {
+        let mut experience = ecs.write_storage::<Experience>();
+        let experience = match experience.get_mut(entity) {
+            Some(experience) => experience,
+            None => return,
+        };
+        experience.xp += amount;
+        while experience.xp >= xp_to_next_level(experience.level) {
+            experience.xp -= xp_to_next_level(experience.level);
+            experience.level += 1;
+            new_levels.push(experience.level);
+        }
+    }
+    for level in new_levels {
+        level_up(ecs, entity, level);
+    }
+}
+
+fn level_up(ecs: &mut World, entity: Entity, level: i32) {
+    if let Some(stats) = ecs.write_storage::<CombatStats>().get_mut(entity) {
+        stats.max_hp += HP_PER_LEVEL;
+        stats.hp = stats.max_hp;
+    }
+    if let Some(pool) = ecs.write_storage::<ManaPool>().get_mut(entity) {
+        pool.max_mana += MANA_PER_LEVEL;
+        pool.mana = pool.max_mana;
+    }
+    LogBuilder::new()
+        .color(format!("Welcome to level {}!", level), magenta())
+        .log(&mut ecs.fetch_mut::<GameLog>());
+
+    if let Some(spell) = level_spell(level) {
+        if !spawner::knows_spell(ecs, entity, spell) {
+            spawner::learn_spell(ecs, entity, spell);
+            LogBuilder::new()
+                .append(format!("You learn to cast {}!", spell))
+                .log(&mut ecs.fetch_mut::<GameLog>());
+        }
+    }
+}
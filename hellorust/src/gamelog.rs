@@ -1,3 +1,151 @@
+use crate::color::*;
+use crate::Renderable;
+use rltk::RGB;
+use specs::prelude::*;
+use std::collections::VecDeque;
+
+/// How many entries the log holds on to before the oldest ones start falling off.
+pub const DEFAULT_LOG_CAPACITY: usize = 500;
+
+/// A piece of a log line in a single color.
+#[derive(Clone)]
+pub struct LogFragment {
+    pub text: String,
+    pub color: RGB,
+}
+
+/// One line of the log, and the turn it happened on.
+#[derive(Clone)]
+pub struct LogEntry {
+    pub turn: i32,
+    pub fragments: Vec<LogFragment>,
+}
+
+impl LogEntry {
+    /// The line without its colors, for searching and text files.
+    pub fn text(&self) -> String {
+        self.fragments
+            .iter()
+            .map(|fragment| fragment.text.as_str())
+            .collect()
+    }
+}
+
 pub struct GameLog {
-    pub entries: Vec<String>,
+    pub entries: VecDeque<LogEntry>,
+    /// Stamped on every entry added from now on.
+    pub turn: i32,
+    pub capacity: usize,
+}
+
+impl GameLog {
+    pub fn new() -> GameLog {
+        GameLog {
+            entries: VecDeque::new(),
+            turn: 0,
+            capacity: DEFAULT_LOG_CAPACITY,
+        }
+    }
+
+    /// Logs a plain, single-colored message.
+    pub fn add<S: ToString>(&mut self, message: S) {
+        LogBuilder::new().append(message).log(self);
+    }
+
+    /// The most recent entries, newest first.
+    pub fn recent(&self, count: usize) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter().rev().take(count)
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.trim();
+    }
+
+    fn push(&mut self, fragments: Vec<LogFragment>) {
+        self.entries.push_back(LogEntry {
+            turn: self.turn,
+            fragments,
+        });
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+    }
+}
+
+impl Default for GameLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Where the log history screen is scrolled to and what it's filtered on.
+#[derive(Default)]
+pub struct LogViewer {
+    /// How many lines up from the newest entry the view starts.
+    pub scroll: usize,
+    pub query: String,
+    /// Keys go into the search query instead of moving around.
+    pub typing: bool,
+}
+
+impl LogViewer {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        self.query.is_empty()
+            || entry
+                .text()
+                .to_lowercase()
+                .contains(&self.query.to_lowercase())
+    }
+}
+
+/// The color an entity is drawn with, so its name in the log matches what's on screen.
+pub fn name_color(ecs: &World, entity: Entity) -> RGB {
+    ecs.read_storage::<Renderable>()
+        .get(entity)
+        .map_or(white(), |render| render.fg)
+}
+
+/// Puts together a log line out of colored fragments:
+/// `LogBuilder::new().name("Goblin", red()).append(" hits you for ").damage(4).log(&mut log)`
+#[derive(Default)]
+pub struct LogBuilder {
+    fragments: Vec<LogFragment>,
+}
+
+impl LogBuilder {
+    pub fn new() -> LogBuilder {
+        LogBuilder {
+            fragments: Vec::new(),
+        }
+    }
+
+    pub fn color<S: ToString>(mut self, text: S, color: RGB) -> LogBuilder {
+        self.fragments.push(LogFragment {
+            text: text.to_string(),
+            color,
+        });
+        self
+    }
+
+    pub fn append<S: ToString>(self, text: S) -> LogBuilder {
+        self.color(text, white())
+    }
+
+    /// Someone or something with a name, in the color it's drawn with on the map.
+    pub fn name<S: ToString>(self, name: S, color: RGB) -> LogBuilder {
+        self.color(name, color)
+    }
+
+    pub fn damage(self, amount: i32) -> LogBuilder {
+        self.color(amount, red())
+    }
+
+    pub fn log(self, log: &mut GameLog) {
+        log.push(self.fragments);
+    }
 }
@@ -1,15 +1,32 @@
 extern crate rltk;
 use crate::components::{HungerClock, HungerState::*};
-use rltk::{Rltk, VirtualKeyCode};
+use rltk::{Rltk, VirtualKeyCode, RGB};
 extern crate specs;
+use super::player::{artifact_recovered, known_spells};
 use super::rex_assets::RexAssets;
 use super::{
-    CombatStats, Equipped, Hidden, InBackpack, Map, Name, Player, Point, Position, RunState, State,
+    effects::aoe_tiles, AreaOfEffect, Character, Charges, CombatStats, Confusion, CursedItem,
+    DefenseBonus, EquipmentSlot, Equippable, Equipped, Experience, Hidden, InBackpack, ManaPool,
+    Map, MeleePowerBonus, Monster, Name, ObfuscatedName, Player, Point, Position, RunState, State,
     Viewshed,
 };
+use crate::character_creation::{
+    class_description, class_name, race_description, race_name, CharacterDraft, CLASSES,
+    MAX_NAME_LENGTH, RACES,
+};
 use crate::color::*;
-use crate::gamelog::GameLog;
+use crate::encumbrance::{carried_weight, carry_capacity, encumbrance, Encumbrance};
+use crate::examine::describe_tile;
+use crate::experience::xp_to_next_level;
+use crate::gamelog::{GameLog, LogEntry, LogViewer};
+use crate::identification::{display_name, is_unidentified, item_display_name, MagicItemNames};
+use crate::keybindings::{key_name, Command, Keymap};
 use crate::saveload_system::save_exists;
+use crate::settings::{
+    apply_settings, cycle, Font, Settings, LOG_LENGTHS, MAPGEN_SPEEDS_MS, TOOLTIP_DELAYS_MS,
+};
+use crate::statistics::{current_score, load_high_scores, RunStats};
+use crate::trade;
 use specs::prelude::*;
 
 // TODO: replace 43 and 79 with const's (maybe directly couple to the ones in map.rs)
@@ -18,6 +35,8 @@ use specs::prelude::*;
 pub enum MainMenuSelection {
     NewGame,
     LoadGame,
+    HighScores,
+    Options,
     Quit,
 }
 
@@ -27,6 +46,46 @@ pub enum MainMenuResult {
     Selected { selected: MainMenuSelection },
 }
 
+#[derive(PartialEq, Copy, Clone)]
+pub enum TradeMode {
+    Buy,
+    Sell,
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum CreationStep {
+    Race,
+    Class,
+    Name,
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum CreationResult {
+    NoSelection { selected: usize },
+    Selected { selected: usize },
+    Back,
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum TargetResult {
+    NoResponse { cursor: Option<Point> },
+    Selected { target: Point },
+    Cancel,
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum LookResult {
+    NoResponse,
+    Moved { x: i32, y: i32 },
+    Cancel,
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum OptionsResult {
+    NoSelection { selected: usize },
+    Close,
+}
+
 #[derive(PartialEq, Copy, Clone)]
 pub enum GameOverResult {
     NoSelection,
@@ -37,7 +96,11 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
     ctx.draw_box(0, 43, 79, 6, white(), black());
 
     let map = ecs.fetch::<Map>();
-    let depth = format!("Depth: {}", map.depth);
+    let depth = if map.depth == 0 {
+        "Town".to_string()
+    } else {
+        format!("Depth: {}", map.depth)
+    };
     ctx.print_color(2, 43, yellow(), black(), &depth);
 
     let combat_stats = ecs.read_storage::<CombatStats>();
@@ -46,12 +109,8 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
     let log = ecs.fetch::<GameLog>();
 
     //GameLog message printing
-    let mut y = 44;
-    for message in log.entries.iter().rev() {
-        if y < 49 {
-            ctx.print(2, y, &message.to_string());
-        }
-        y += 1;
+    for (y, entry) in (45..49).zip(log.recent(4)) {
+        draw_log_entry(ctx, 2, y, entry, 77);
     }
 
     for (_player, stats, hunger) in (&players, &combat_stats, &hunger_clocks).join() {
@@ -68,26 +127,80 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         ctx.print_color(71, 42, text_color, black(), hunger_text);
     }
 
+    let player_entity = ecs.fetch::<Entity>();
+    if let Some(pool) = ecs.read_storage::<ManaPool>().get(*player_entity) {
+        let mana = format!(" MP: {}/{} ", pool.mana, pool.max_mana);
+        ctx.print_color(12, 44, white(), black(), &mana);
+        ctx.draw_bar_horizontal(28, 44, 51, pool.mana, pool.max_mana, blue(), black());
+    }
+    if let Some(experience) = ecs.read_storage::<Experience>().get(*player_entity) {
+        let level = format!("Level {}", experience.level);
+        ctx.print_color(2, 44, magenta(), black(), &level);
+    }
+
+    // Spell hotbar along the bottom edge
+    let mut x = 2;
+    for (i, (_spell, name, mana_cost)) in known_spells(ecs).iter().enumerate().take(9) {
+        let entry = format!("{}:{}({})", i + 1, name, mana_cost);
+        ctx.print_color(x, 49, cyan(), black(), &entry);
+        x += entry.len() as i32 + 1;
+    }
+
+    let gold = format!("Gold: {}", trade::gold(ecs, *player_entity));
+    ctx.print_color(46, 42, yellow(), black(), &gold);
+    match encumbrance(ecs, *player_entity) {
+        Encumbrance::Unburdened => {}
+        Encumbrance::Burdened => ctx.print_color(60, 42, orange(), black(), "Burdened"),
+        Encumbrance::Overloaded => ctx.print_color(59, 42, red(), black(), "Overloaded"),
+    }
+
     let mouse_pos = ctx.mouse_pos();
     ctx.set_bg(mouse_pos.0, mouse_pos.1, magenta());
     draw_tooltips(ecs, ctx);
 }
 
+/// Where the mouse is resting, and for how long, so tooltips can wait a moment.
+#[derive(Default)]
+pub struct MouseHover {
+    pub pos: (i32, i32),
+    pub resting_ms: f32,
+}
+
 fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
     let map = ecs.fetch::<Map>();
     let names = ecs.read_storage::<Name>();
+    let obfuscated_names = ecs.read_storage::<ObfuscatedName>();
+    let magic_item_names = ecs.fetch::<MagicItemNames>();
     let positions = ecs.read_storage::<Position>();
     let hidden = ecs.read_storage::<Hidden>();
+    let entities = ecs.entities();
 
     let mouse_pos = ctx.mouse_pos();
     if mouse_pos.0 >= map.width || mouse_pos.1 >= map.height {
         return;
     }
+    {
+        let mut hover = ecs.fetch_mut::<MouseHover>();
+        if hover.pos != mouse_pos {
+            hover.pos = mouse_pos;
+            hover.resting_ms = 0.0;
+        } else {
+            hover.resting_ms += ctx.frame_time_ms;
+        }
+        if hover.resting_ms < ecs.fetch::<Settings>().tooltip_delay_ms {
+            return;
+        }
+    }
 
     let mut tooltip: Vec<String> = Vec::new();
-    for (name, position, _hidden) in (&names, &positions, !&hidden).join() {
+    for (entity, _name, position, _hidden) in (&entities, &names, &positions, !&hidden).join() {
         if position.x == mouse_pos.0 && position.y == mouse_pos.1 {
-            tooltip.push(name.name.to_string());
+            tooltip.push(display_name(
+                entity,
+                &names,
+                &obfuscated_names,
+                &magic_item_names,
+            ));
         }
     }
 
@@ -106,29 +219,25 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
             let arrow_pos = Point::new(mouse_pos.0 - 2, mouse_pos.1);
             let left_x = mouse_pos.0 - width;
 
-            let mut y = mouse_pos.1;
-            for tip in tooltip.iter() {
-                ctx.print_color(left_x, y, white(), grey(), &tip.to_string());
+            for (y, tip) in (mouse_pos.1..).zip(tooltip.iter()) {
+                ctx.print_color(left_x, y, white(), grey(), tip.to_string());
                 let padding = (width - tip.len() as i32) - 1;
                 for i in 0..padding {
-                    ctx.print_color(arrow_pos.x - i, y, white(), grey(), &" ".to_string());
+                    ctx.print_color(arrow_pos.x - i, y, white(), grey(), " ".to_string());
                 }
-                y += 1;
             }
-            ctx.print_color(arrow_pos.x, arrow_pos.y, white(), grey(), &"->".to_string());
+            ctx.print_color(arrow_pos.x, arrow_pos.y, white(), grey(), "->".to_string());
         } else {
             let arrow_pos = Point::new(mouse_pos.0 + 1, mouse_pos.1);
             let left_x = mouse_pos.0 + 3;
-            let mut y = mouse_pos.1;
-            for s in tooltip.iter() {
-                ctx.print_color(left_x + 1, y, white(), grey(), &s.to_string());
+            for (y, s) in (mouse_pos.1..).zip(tooltip.iter()) {
+                ctx.print_color(left_x + 1, y, white(), grey(), s.to_string());
                 let padding = (width - s.len() as i32) - 1;
                 for i in 0..padding {
-                    ctx.print_color(arrow_pos.x + 1 + i, y, white(), grey(), &" ".to_string());
+                    ctx.print_color(arrow_pos.x + 1 + i, y, white(), grey(), " ".to_string());
                 }
-                y += 1;
             }
-            ctx.print_color(arrow_pos.x, arrow_pos.y, white(), grey(), &"<-".to_string());
+            ctx.print_color(arrow_pos.x, arrow_pos.y, white(), grey(), "<-".to_string());
         }
     }
 }
@@ -138,53 +247,68 @@ pub enum ItemMenuResult {
     Cancel,
     NoResponse,
     Selected,
+    ChangePage { page: usize },
 }
 
-pub fn show_inventory(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
-    let player_entity = gs.ecs.fetch::<Entity>();
-    let names = gs.ecs.read_storage::<Name>();
-    let backpack = gs.ecs.read_storage::<InBackpack>();
-    let entities = gs.ecs.entities();
+/// How many items fit on one page of an item menu.
+const ITEM_MENU_PAGE_SIZE: usize = 20;
 
-    // all the items in the player_entity's inventory
-    let inventory = (&backpack, &names)
-        .join()
-        .filter(|item| item.0.owner == *player_entity);
-    let count = inventory.count() as i32;
+/// Draws a lettered list of items and returns the one the player picked.
+/// Long lists are split into pages, flipped with PageUp/PageDown or the arrow keys.
+fn item_menu(
+    ctx: &mut Rltk,
+    title: &str,
+    title_color: rltk::RGB,
+    close_msg: &str,
+    items: &[(Entity, String)],
+    page: usize,
+) -> (ItemMenuResult, Option<Entity>) {
+    let page_count = usize::max(1, items.len().div_ceil(ITEM_MENU_PAGE_SIZE));
+    let page = usize::min(page, page_count - 1);
+    let first = page * ITEM_MENU_PAGE_SIZE;
+    let last = usize::min(items.len(), first + ITEM_MENU_PAGE_SIZE);
+    let page_items = &items[first..last];
 
-    let mut equippable: Vec<Entity> = Vec::new();
-    let mut y = 25 - (count / 2);
+    let count = page_items.len() as i32;
+    let y = 25 - (count / 2);
     ctx.draw_box(15, y - 2, 31, count + 3, white(), black());
-    let title = "Inventory";
-    ctx.print_color(18, y - 2, yellow(), black(), title);
-    let close_msg = "Esc to close";
+    ctx.print_color(18, y - 2, title_color, black(), title);
     ctx.print_color(18, y + count + 1, yellow(), black(), close_msg);
+    if page_count > 1 {
+        ctx.print_color(
+            37,
+            y + count + 1,
+            yellow(),
+            black(),
+            format!("< {}/{} >", page + 1, page_count),
+        );
+    }
 
-    let mut j = 0;
-    for (entity, _backpack, name) in (&entities, &backpack, &names)
-        .join()
-        .filter(|item| item.1.owner == *player_entity)
-    {
+    for (j, (_entity, name)) in page_items.iter().enumerate() {
+        let y = y + j as i32;
         ctx.set(17, y, white(), black(), rltk::to_cp437('('));
         ctx.set(18, y, yellow(), black(), 97 + j as u8); //ASCII code 97 = a
         ctx.set(19, y, white(), black(), rltk::to_cp437(')'));
 
-        ctx.print(21, y, &name.name.to_string());
-        equippable.push(entity);
-        y += 1;
-        j += 1;
+        ctx.print(21, y, name);
     }
 
     match ctx.key {
         None => (ItemMenuResult::NoResponse, None),
         Some(key) => match key {
             VirtualKeyCode::Escape => (ItemMenuResult::Cancel, None),
+            VirtualKeyCode::PageDown | VirtualKeyCode::Right if page + 1 < page_count => {
+                (ItemMenuResult::ChangePage { page: page + 1 }, None)
+            }
+            VirtualKeyCode::PageUp | VirtualKeyCode::Left if page > 0 => {
+                (ItemMenuResult::ChangePage { page: page - 1 }, None)
+            }
             _ => {
                 let selection = rltk::letter_to_option(key);
                 if selection > -1 && selection < count {
                     return (
                         ItemMenuResult::Selected,
-                        Some(equippable[selection as usize]),
+                        Some(page_items[selection as usize].0),
                     );
                 }
                 (ItemMenuResult::NoResponse, None)
@@ -193,69 +317,187 @@ pub fn show_inventory(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
     }
 }
 
-pub fn show_drop_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
-    let player_entity = gs.ecs.fetch::<Entity>();
-    let names = gs.ecs.read_storage::<Name>();
-    let backpack = gs.ecs.read_storage::<InBackpack>();
-    let entities = gs.ecs.entities();
-
-    // all the items in the player_entity's inventory
-    let inventory = (&backpack, &names)
-        .join()
-        .filter(|item| item.0.owner == *player_entity);
-    let count = inventory.count() as i32;
+/// All the items in the player's backpack, by the name the player knows them by.
+pub fn backpack_items(ecs: &World) -> Vec<(Entity, String)> {
+    let player_entity = *ecs.fetch::<Entity>();
+    items_owned_by(ecs, player_entity)
+}
 
-    let mut equippable: Vec<Entity> = Vec::new();
-    let mut y = 25 - (count / 2);
-    ctx.draw_box(15, y - 2, 31, count + 3, white(), black());
-    let title = "Drop which item?";
-    ctx.print_color(18, y - 2, red(), black(), title);
-    let close_msg = "Esc to close";
-    ctx.print_color(18, y + count + 1, yellow(), black(), close_msg);
+/// All the items in `owner`'s backpack, by the name the player knows them by.
+/// Items that look the same are stacked into one line with a count.
+fn items_owned_by(ecs: &World, owner: Entity) -> Vec<(Entity, String)> {
+    let names = ecs.read_storage::<Name>();
+    let obfuscated_names = ecs.read_storage::<ObfuscatedName>();
+    let magic_item_names = ecs.fetch::<MagicItemNames>();
+    let backpack = ecs.read_storage::<InBackpack>();
+    let charges = ecs.read_storage::<Charges>();
+    let entities = ecs.entities();
 
-    let mut j = 0;
-    for (entity, _backpack, name) in (&entities, &backpack, &names)
+    let mut stacks: Vec<(Entity, String, i32)> = Vec::new();
+    for (entity, _backpack) in (&entities, &backpack)
         .join()
-        .filter(|item| item.1.owner == *player_entity)
+        .filter(|item| item.1.owner == owner)
     {
-        ctx.set(17, y, white(), black(), rltk::to_cp437('('));
-        ctx.set(18, y, yellow(), black(), 97 + j as u8); //ASCII code 97 = a
-        ctx.set(19, y, white(), black(), rltk::to_cp437(')'));
-
-        ctx.print(21, y, &name.name.to_string());
-        equippable.push(entity);
-        y += 1;
-        j += 1;
+        let name = display_name(entity, &names, &obfuscated_names, &magic_item_names);
+        let name = match charges.get(entity) {
+            Some(c) if c.charges < 1 => format!("{} (empty)", name),
+            Some(c) => format!("{} ({}/{})", name, c.charges, c.max_charges),
+            None => name,
+        };
+        match stacks.iter_mut().find(|stack| stack.1 == name) {
+            Some(stack) => stack.2 += 1,
+            None => stacks.push((entity, name, 1)),
+        }
     }
 
-    match ctx.key {
-        None => (ItemMenuResult::NoResponse, None),
-        Some(key) => match key {
-            VirtualKeyCode::Escape => (ItemMenuResult::Cancel, None),
-            _ => {
-                let selection = rltk::letter_to_option(key);
-                if selection > -1 && selection < count {
-                    return (
-                        ItemMenuResult::Selected,
-                        Some(equippable[selection as usize]),
-                    );
-                }
-                (ItemMenuResult::NoResponse, None)
+    stacks
+        .into_iter()
+        .map(|(entity, name, count)| {
+            if count > 1 {
+                (entity, format!("{} (x{})", name, count))
+            } else {
+                (entity, name)
             }
-        },
-    }
+        })
+        .collect()
+}
+
+pub fn show_inventory(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    page: usize,
+) -> (ItemMenuResult, Option<Entity>) {
+    let items = backpack_items(&gs.ecs);
+    let title = {
+        let player_entity = gs.ecs.fetch::<Entity>();
+        let combat_stats = gs.ecs.read_storage::<CombatStats>();
+        match combat_stats.get(*player_entity) {
+            Some(stats) => format!(
+                "Inventory ({:.1}/{:.0} lbs)",
+                carried_weight(&gs.ecs, *player_entity),
+                carry_capacity(stats)
+            ),
+            None => "Inventory".to_string(),
+        }
+    };
+    item_menu(ctx, &title, yellow(), "Esc to close", &items, page)
+}
+
+pub fn show_drop_item_menu(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    page: usize,
+) -> (ItemMenuResult, Option<Entity>) {
+    let items = backpack_items(&gs.ecs);
+    item_menu(ctx, "Drop which item?", red(), "Esc to close", &items, page)
+}
+
+/// The shop window: the vendor's stock when buying, your backpack when selling.
+/// Tab switches between the two.
+pub fn trade_menu(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    vendor: Entity,
+    mode: TradeMode,
+    page: usize,
+) -> (ItemMenuResult, Option<Entity>) {
+    let player_entity = *gs.ecs.fetch::<Entity>();
+    let gold = trade::gold(&gs.ecs, player_entity);
+    let (title, items) = match mode {
+        TradeMode::Buy => (
+            format!("Buy ({} gold) - Tab: sell", gold),
+            items_owned_by(&gs.ecs, vendor)
+                .into_iter()
+                .map(|(item, name)| {
+                    let price = trade::buy_price(&gs.ecs, vendor, item);
+                    (item, format!("{} - {}g", name, price))
+                })
+                .collect::<Vec<(Entity, String)>>(),
+        ),
+        TradeMode::Sell => (
+            format!("Sell ({} gold) - Tab: buy", gold),
+            backpack_items(&gs.ecs)
+                .into_iter()
+                .map(|(item, name)| {
+                    let price = trade::sell_price(&gs.ecs, item);
+                    (item, format!("{} - {}g", name, price))
+                })
+                .collect::<Vec<(Entity, String)>>(),
+        ),
+    };
+    item_menu(ctx, &title, yellow(), "Esc to leave", &items, page)
+}
+
+/// Lists the unidentified items in the backpack, except for the kind of
+/// scroll that is being read.
+pub fn identify_menu(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    scroll: Entity,
+    page: usize,
+) -> (ItemMenuResult, Option<Entity>) {
+    let items: Vec<(Entity, String)> = {
+        let names = gs.ecs.read_storage::<Name>();
+        let scroll_name = names.get(scroll).map(|n| n.name.clone());
+        backpack_items(&gs.ecs)
+            .into_iter()
+            .filter(|(entity, _name)| {
+                is_unidentified(&gs.ecs, *entity)
+                    && names.get(*entity).map(|n| n.name.clone()) != scroll_name
+            })
+            .collect()
+    };
+    item_menu(
+        ctx,
+        "Identify which item?",
+        cyan(),
+        "Esc to cancel",
+        &items,
+        page,
+    )
+}
+
+/// Lists the cursed items the player is wearing.
+pub fn remove_curse_menu(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    page: usize,
+) -> (ItemMenuResult, Option<Entity>) {
+    let items: Vec<(Entity, String)> = {
+        let player_entity = gs.ecs.fetch::<Entity>();
+        let names = gs.ecs.read_storage::<Name>();
+        let equipped = gs.ecs.read_storage::<Equipped>();
+        let cursed = gs.ecs.read_storage::<CursedItem>();
+        let entities = gs.ecs.entities();
+
+        // Only what you're wearing: a curse in the backpack hasn't shown itself yet
+        (&entities, &equipped, &cursed, &names)
+            .join()
+            .filter(|(_entity, equipped, _cursed, _name)| equipped.owner == *player_entity)
+            .map(|(entity, _equipped, _cursed, name)| (entity, name.name.to_string()))
+            .collect()
+    };
+    item_menu(
+        ctx,
+        "Remove curse from which item?",
+        cyan(),
+        "Esc to cancel",
+        &items,
+        page,
+    )
 }
 
 pub fn ranged_target(
     gs: &mut State,
     ctx: &mut Rltk,
     range: i32,
-) -> (ItemMenuResult, Option<Point>) {
+    item: Entity,
+    cursor: Option<Point>,
+) -> TargetResult {
     let player_entity = gs.ecs.fetch::<Entity>();
-    let player_pos = gs.ecs.fetch::<Point>();
+    let player_pos = *gs.ecs.fetch::<Point>();
     let viewsheds = gs.ecs.read_storage::<Viewshed>();
-
-    ctx.print_color(5, 0, yellow(), black(), "Select Target:");
+    let map = gs.ecs.fetch::<Map>();
 
     // "Highlight available target cells"
     let mut available_cells = Vec::new();
@@ -265,48 +507,233 @@ pub fn ranged_target(
         Some(visible) => {
             // "We have a viewshed"
             for idx in visible.visible_tiles.iter() {
-                let distance = rltk::DistanceAlg::Pythagoras.distance2d(*player_pos, *idx);
+                let distance = rltk::DistanceAlg::Pythagoras.distance2d(player_pos, *idx);
                 if distance <= range as f32 {
                     ctx.set_bg(idx.x, idx.y, blue());
-                    available_cells.push(idx);
+                    available_cells.push(*idx);
                 }
             }
         }
-        None => return (ItemMenuResult::Cancel, None),
+        None => return TargetResult::Cancel,
     }
 
-    // Draw mouse cursor
-    let mouse_pos = ctx.mouse_pos();
-    let mut valid_target = false;
-    for idx in available_cells.iter() {
-        if idx.x == mouse_pos.0 && idx.y == mouse_pos.1 {
-            valid_target = true;
+    // Hostiles in range, closest first, for cycling through with the keyboard
+    let mut hostiles: Vec<Point> = {
+        let positions = gs.ecs.read_storage::<Position>();
+        let monsters = gs.ecs.read_storage::<Monster>();
+        let hidden = gs.ecs.read_storage::<Hidden>();
+        (&positions, &monsters, !&hidden)
+            .join()
+            .map(|(pos, _monster, _hidden)| Point::new(pos.x, pos.y))
+            .filter(|pos| available_cells.contains(pos))
+            .collect()
+    };
+    hostiles.sort_by(|a, b| {
+        let distance_a = rltk::DistanceAlg::Pythagoras.distance2d(player_pos, *a);
+        let distance_b = rltk::DistanceAlg::Pythagoras.distance2d(player_pos, *b);
+        distance_a.partial_cmp(&distance_b).unwrap()
+    });
+    let cursor = cursor.unwrap_or_else(|| hostiles.first().copied().unwrap_or(player_pos));
+    let valid_cursor = available_cells.contains(&cursor);
+
+    // The line the shot travels along, and where a blast would reach
+    for step in rltk::line2d(rltk::LineAlg::Bresenham, player_pos, cursor)
+        .iter()
+        .skip(1)
+    {
+        ctx.set_bg(step.x, step.y, grey());
+    }
+    let mut victims: Vec<String> = Vec::new();
+    if let Some(aoe) = gs.ecs.read_storage::<AreaOfEffect>().get(item) {
+        let names = gs.ecs.read_storage::<Name>();
+        let hidden = gs.ecs.read_storage::<Hidden>();
+        for idx in aoe_tiles(&map, cursor, aoe.radius) {
+            let (x, y) = (idx as i32 % map.width, idx as i32 / map.width);
+            ctx.set_bg(x, y, orange());
+            if !map.visible_tiles[idx] {
+                continue;
+            }
+            for entity in map.tile_content[idx].iter() {
+                if *entity == *player_entity {
+                    victims.push("you".to_string());
+                } else if let (Some(name), None) = (names.get(*entity), hidden.get(*entity)) {
+                    victims.push(name.name.clone());
+                }
+            }
+        }
+    } else if let Some(idx) = valid_cursor.then(|| map.xy_idx(cursor.x, cursor.y)) {
+        let names = gs.ecs.read_storage::<Name>();
+        let hidden = gs.ecs.read_storage::<Hidden>();
+        for entity in map.tile_content[idx].iter() {
+            if let (Some(name), None) = (names.get(*entity), hidden.get(*entity)) {
+                victims.push(name.name.clone());
+            }
         }
     }
+    ctx.set_bg(
+        cursor.x,
+        cursor.y,
+        if valid_cursor { cyan() } else { red() },
+    );
 
-    if valid_target {
+    ctx.print_color(
+        5,
+        0,
+        yellow(),
+        black(),
+        "Select Target: Tab/arrows cycle, Enter fires, Escape cancels",
+    );
+    if !victims.is_empty() {
+        let mut hits = format!("Hits: {}", victims.join(", "));
+        hits.truncate(70);
+        ctx.print_color(5, 1, orange(), black(), hits);
+    }
+
+    // The mouse still works: hover to look, click to fire
+    let mouse_pos = ctx.mouse_pos();
+    let mouse_point = Point::new(mouse_pos.0, mouse_pos.1);
+    if available_cells.contains(&mouse_point) {
         ctx.set_bg(mouse_pos.0, mouse_pos.1, cyan());
         if ctx.left_click {
-            return (
-                ItemMenuResult::Selected,
-                Some(Point::new(mouse_pos.0, mouse_pos.1)),
-            );
+            return TargetResult::Selected {
+                target: mouse_point,
+            };
         }
     } else {
         ctx.set_bg(mouse_pos.0, mouse_pos.1, red());
         if ctx.left_click {
-            return (ItemMenuResult::Cancel, None);
+            return TargetResult::Cancel;
         }
     }
 
-    (ItemMenuResult::NoResponse, None)
+    let key = match ctx.key {
+        None => {
+            return TargetResult::NoResponse {
+                cursor: Some(cursor),
+            }
+        }
+        Some(key) => key,
+    };
+    let current = hostiles.iter().position(|pos| *pos == cursor);
+    let cycle = |forward: bool| -> Point {
+        if hostiles.is_empty() {
+            return cursor;
+        }
+        let count = hostiles.len();
+        let next = match (current, forward) {
+            (Some(i), true) => (i + 1) % count,
+            (Some(i), false) => (i + count - 1) % count,
+            (None, _) => 0,
+        };
+        hostiles[next]
+    };
+    let cursor = match key {
+        VirtualKeyCode::Escape => return TargetResult::Cancel,
+        VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter if valid_cursor => {
+            return TargetResult::Selected { target: cursor }
+        }
+        VirtualKeyCode::Tab => cycle(!ctx.shift),
+        VirtualKeyCode::Right | VirtualKeyCode::Down => cycle(true),
+        VirtualKeyCode::Left | VirtualKeyCode::Up => cycle(false),
+        // The other movement keys aim at any tile, to put a blast just where you want it
+        _ => match gs.ecs.fetch::<Keymap>().direction(key) {
+            Some((dx, dy)) => Point::new(
+                (cursor.x + dx).clamp(0, map.width - 1),
+                (cursor.y + dy).clamp(0, map.height - 1),
+            ),
+            None => cursor,
+        },
+    };
+    TargetResult::NoResponse {
+        cursor: Some(cursor),
+    }
+}
+
+const LOOK_PANEL_WIDTH: i32 = 36;
+
+/// Moves a cursor around the map with the movement keys and describes what's under it.
+pub fn look_mode(ecs: &World, ctx: &mut Rltk, x: i32, y: i32) -> LookResult {
+    let map = ecs.fetch::<Map>();
+    let idx = map.xy_idx(x, y);
+    let cursor_color = if map.visible_tiles[idx] {
+        cyan()
+    } else {
+        grey()
+    };
+    ctx.set_bg(x, y, cursor_color);
+    ctx.print_color(
+        5,
+        0,
+        yellow(),
+        black(),
+        "Look: move the cursor, Escape to stop",
+    );
+
+    let mut lines: Vec<(String, RGB)> = Vec::new();
+    for description in describe_tile(ecs, x, y) {
+        lines.push((description.title, yellow()));
+        for detail in description.details {
+            for line in wrap(&detail, LOOK_PANEL_WIDTH as usize - 4) {
+                lines.push((format!(" {}", line), white()));
+            }
+        }
+    }
+    // keep the panel out of the way of the cursor
+    let panel_x = if x > 40 { 1 } else { 78 - LOOK_PANEL_WIDTH };
+    ctx.draw_box(
+        panel_x,
+        1,
+        LOOK_PANEL_WIDTH,
+        lines.len() as i32 + 1,
+        white(),
+        black(),
+    );
+    for (y, (line, color)) in (2..).zip(lines.iter()) {
+        ctx.print_color(panel_x + 1, y, *color, black(), line);
+    }
+
+    let key = match ctx.key {
+        None => return LookResult::NoResponse,
+        Some(key) => key,
+    };
+    let keymap = ecs.fetch::<Keymap>();
+    if key == VirtualKeyCode::Escape || keymap.command_for(key) == Some(Command::Look) {
+        return LookResult::Cancel;
+    }
+    match keymap.direction(key) {
+        Some((dx, dy)) => LookResult::Moved {
+            x: (x + dx).clamp(0, map.width - 1),
+            y: (y + dy).clamp(0, map.height - 1),
+        },
+        None => LookResult::NoResponse,
+    }
+}
+
+/// Splits `text` into lines of at most `width` characters, on word boundaries.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > width {
+            lines.push(line);
+            line = String::new();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
 }
 
 pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
     let runstate = gs.ecs.fetch::<RunState>();
     let assets = gs.ecs.fetch::<RexAssets>();
     ctx.render_xp_sprite(&assets.menu, 0, 0);
-    ctx.draw_box_double(24, 16, 31, 12, grey(), black());
+    ctx.draw_box_double(24, 16, 31, 16, grey(), black());
     ctx.print_color_centered(17, yellow(), black(), "Hello Rust World");
     ctx.print_color_centered(19, grey(), black(), "Use arrows or WASD to move");
     if let RunState::MainMenu {
@@ -329,10 +756,22 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
             }
         }
 
+        if selection == MainMenuSelection::HighScores {
+            ctx.print_color_centered(26, magenta(), black(), "High Scores");
+        } else {
+            ctx.print_color_centered(26, white(), black(), "High Scores");
+        }
+
+        if selection == MainMenuSelection::Options {
+            ctx.print_color_centered(28, magenta(), black(), "Options");
+        } else {
+            ctx.print_color_centered(28, white(), black(), "Options");
+        }
+
         if selection == MainMenuSelection::Quit {
-            ctx.print_color_centered(26, magenta(), black(), "Quit");
+            ctx.print_color_centered(30, magenta(), black(), "Quit");
         } else {
-            ctx.print_color_centered(26, white(), black(), "Quit");
+            ctx.print_color_centered(30, white(), black(), "Quit");
         }
 
         match ctx.key {
@@ -348,35 +787,37 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
                     }
                 }
                 VirtualKeyCode::Up | VirtualKeyCode::W => {
-                    let newselection;
-                    match selection {
-                        MainMenuSelection::NewGame => newselection = MainMenuSelection::Quit,
-                        MainMenuSelection::LoadGame => newselection = MainMenuSelection::NewGame,
-                        MainMenuSelection::Quit => {
-                            newselection = if save_exists() {
+                    let newselection = match selection {
+                        MainMenuSelection::NewGame => MainMenuSelection::Quit,
+                        MainMenuSelection::LoadGame => MainMenuSelection::NewGame,
+                        MainMenuSelection::HighScores => {
+                            if save_exists() {
                                 MainMenuSelection::LoadGame
                             } else {
                                 MainMenuSelection::NewGame
                             }
                         }
-                    }
+                        MainMenuSelection::Options => MainMenuSelection::HighScores,
+                        MainMenuSelection::Quit => MainMenuSelection::Options,
+                    };
                     return MainMenuResult::NoSelection {
                         selected: newselection,
                     };
                 }
                 VirtualKeyCode::Down | VirtualKeyCode::S => {
-                    let newselection;
-                    match selection {
+                    let newselection = match selection {
                         MainMenuSelection::NewGame => {
-                            newselection = if save_exists() {
+                            if save_exists() {
                                 MainMenuSelection::LoadGame
                             } else {
-                                MainMenuSelection::Quit
+                                MainMenuSelection::HighScores
                             }
                         }
-                        MainMenuSelection::LoadGame => newselection = MainMenuSelection::Quit,
-                        MainMenuSelection::Quit => newselection = MainMenuSelection::NewGame,
-                    }
+                        MainMenuSelection::LoadGame => MainMenuSelection::HighScores,
+                        MainMenuSelection::HighScores => MainMenuSelection::Options,
+                        MainMenuSelection::Options => MainMenuSelection::Quit,
+                        MainMenuSelection::Quit => MainMenuSelection::NewGame,
+                    };
                     return MainMenuResult::NoSelection {
                         selected: newselection,
                     };
@@ -400,64 +841,497 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
     }
 }
 
-pub fn remove_equipment_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
+/// The paper doll: one line per body slot, in head-to-toe order.
+const EQUIPMENT_SLOTS: [(&str, EquipmentSlot); 11] = [
+    ("Head", EquipmentSlot::Head),
+    ("Amulet", EquipmentSlot::Amulet),
+    ("Torso", EquipmentSlot::Torso),
+    ("Hands", EquipmentSlot::Hands),
+    ("Melee", EquipmentSlot::Melee),
+    ("Shield", EquipmentSlot::Shield),
+    ("Ranged", EquipmentSlot::Ranged),
+    ("Left Ring", EquipmentSlot::Ring),
+    ("Right Ring", EquipmentSlot::Ring),
+    ("Legs", EquipmentSlot::Legs),
+    ("Feet", EquipmentSlot::Feet),
+];
+
+/// What `owner` is wearing, one entry per paper doll slot, in head-to-toe order.
+pub fn paper_doll(ecs: &World, owner: Entity) -> Vec<(&'static str, Option<Entity>)> {
+    let equipped = ecs.read_storage::<Equipped>();
+    let entities = ecs.entities();
+    let worn: Vec<(Entity, EquipmentSlot)> = (&entities, &equipped)
+        .join()
+        .filter(|(_entity, equipped)| equipped.owner == owner)
+        .map(|(entity, equipped)| (entity, equipped.slot))
+        .collect();
+
+    let mut rings_seen = 0;
+    EQUIPMENT_SLOTS
+        .iter()
+        .map(|(label, slot)| {
+            let item = if *slot == EquipmentSlot::Ring {
+                rings_seen += 1;
+                worn.iter()
+                    .filter(|(_entity, s)| *s == EquipmentSlot::Ring)
+                    .nth(rings_seen - 1)
+                    .map(|(entity, _slot)| *entity)
+            } else {
+                worn.iter()
+                    .find(|(_entity, s)| s == slot)
+                    .map(|(entity, _slot)| *entity)
+            };
+            (*label, item)
+        })
+        .collect()
+}
+
+/// Shows what the player is wearing in every slot; picking an occupied slot takes that item off.
+pub fn equipment_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
     let player_entity = gs.ecs.fetch::<Entity>();
     let names = gs.ecs.read_storage::<Name>();
-    let backpack = gs.ecs.read_storage::<Equipped>();
-    let entities = gs.ecs.entities();
+    let equippable = gs.ecs.read_storage::<Equippable>();
+    let cursed = gs.ecs.read_storage::<CursedItem>();
+    let power_bonus = gs.ecs.read_storage::<MeleePowerBonus>();
+    let defense_bonus = gs.ecs.read_storage::<DefenseBonus>();
 
-    let inventory = (&backpack, &names)
-        .join()
-        .filter(|item| item.0.owner == *player_entity);
-    let count = inventory.count();
+    let worn = paper_doll(&gs.ecs, *player_entity);
+    let two_hander = worn.iter().flat_map(|(_label, item)| item).find(|item| {
+        equippable
+            .get(**item)
+            .is_some_and(|equippable| equippable.two_handed)
+    });
 
-    let mut y = (25 - (count / 2)) as i32;
-    ctx.draw_box(15, y - 2, 31, (count + 3) as i32, white(), black());
-    ctx.print_color(18, y - 2, yellow(), black(), "Remove Which Item?");
-    ctx.print_color(
-        18,
-        y + count as i32 + 1,
-        yellow(),
-        black(),
-        "Escape to cancel",
-    );
+    let count = EQUIPMENT_SLOTS.len() as i32;
+    let y = 25 - (count / 2);
+    ctx.draw_box(15, y - 2, 45, count + 5, white(), black());
+    ctx.print_color(18, y - 2, yellow(), black(), "Equipment");
+    ctx.print_color(18, y + count + 3, yellow(), black(), "Escape to cancel");
 
-    let mut equippable: Vec<Entity> = Vec::new();
-    let mut j = 0;
-    for (entity, _pack, name) in (&entities, &backpack, &names)
-        .join()
-        .filter(|item| item.1.owner == *player_entity)
+    let mut slot_items: Vec<Option<Entity>> = Vec::new();
+    for (j, ((label, slot), (_label, item))) in EQUIPMENT_SLOTS.iter().zip(worn.iter()).enumerate()
     {
+        let y = y + j as i32;
+        let item = *item;
+        slot_items.push(item);
+
         ctx.set(17, y, white(), black(), rltk::to_cp437('('));
-        ctx.set(18, y, yellow(), black(), 97 + j as u8);
+        ctx.set(18, y, yellow(), black(), 97 + j as u8); //ASCII code 97 = a
         ctx.set(19, y, white(), black(), rltk::to_cp437(')'));
+        ctx.print_color(21, y, grey(), black(), format!("{}:", label));
 
-        ctx.print(21, y, &name.name.to_string());
-        equippable.push(entity);
-        y += 1;
-        j += 1;
+        match item {
+            Some(item) => {
+                let name = &names.get(item).unwrap().name;
+                // once it's on you, you know it's cursed
+                if cursed.get(item).is_some() {
+                    ctx.print_color(33, y, red(), black(), format!("{} (cursed)", name));
+                } else {
+                    ctx.print(33, y, name);
+                }
+            }
+            None if *slot == EquipmentSlot::Shield && two_hander.is_some() => {
+                ctx.print_color(33, y, grey(), black(), "(two-handed weapon)");
+            }
+            None => ctx.print_color(33, y, grey(), black(), "-"),
+        }
     }
 
+    let power: i32 = worn
+        .iter()
+        .flat_map(|(_label, item)| item)
+        .filter_map(|item| power_bonus.get(*item))
+        .map(|bonus| bonus.power)
+        .sum();
+    let defense: i32 = worn
+        .iter()
+        .flat_map(|(_label, item)| item)
+        .filter_map(|item| defense_bonus.get(*item))
+        .map(|bonus| bonus.defense)
+        .sum();
+    ctx.print_color(
+        21,
+        y + count + 1,
+        white(),
+        black(),
+        format!("Power {:+}  Defense {:+}", power, defense),
+    );
+
     match ctx.key {
         None => (ItemMenuResult::NoResponse, None),
-        Some(key) => match key {
-            VirtualKeyCode::Escape => (ItemMenuResult::Cancel, None),
-            _ => {
-                let selection = rltk::letter_to_option(key);
-                if selection > -1 && selection < count as i32 {
-                    return (
-                        ItemMenuResult::Selected,
-                        Some(equippable[selection as usize]),
-                    );
+        Some(VirtualKeyCode::Escape) => (ItemMenuResult::Cancel, None),
+        Some(key) => {
+            let selection = rltk::letter_to_option(key);
+            if selection > -1 && selection < count {
+                if let Some(item) = slot_items[selection as usize] {
+                    return (ItemMenuResult::Selected, Some(item));
                 }
-                (ItemMenuResult::NoResponse, None)
             }
-        },
+            (ItemMenuResult::NoResponse, None)
+        }
+    }
+}
+
+/// Everything about the player on one screen: stats, what they add up to, and what's worn.
+pub fn character_sheet(ecs: &World, ctx: &mut Rltk) -> ItemMenuResult {
+    let player_entity = *ecs.fetch::<Entity>();
+    let power_bonus = ecs.read_storage::<MeleePowerBonus>();
+    let defense_bonus = ecs.read_storage::<DefenseBonus>();
+    let run_stats = ecs.fetch::<RunStats>();
+    let map = ecs.fetch::<Map>();
+
+    ctx.draw_box(3, 2, 73, 39, white(), black());
+    ctx.print_color(6, 2, yellow(), black(), "Character");
+    ctx.print_color(6, 41, yellow(), black(), "Escape to close");
+
+    let mut y = 4;
+    let name = ecs
+        .read_storage::<Name>()
+        .get(player_entity)
+        .map_or("Player".to_string(), |name| name.name.clone());
+    ctx.print_color(6, y, white(), black(), &name);
+    if let Some(character) = ecs.read_storage::<Character>().get(player_entity) {
+        ctx.print_color(
+            7 + name.len() as i32,
+            y,
+            grey(),
+            black(),
+            format!(
+                "the {} {}",
+                race_name(character.race),
+                class_name(character.class)
+            ),
+        );
+    }
+
+    let worn = paper_doll(ecs, player_entity);
+    let equipment_power: i32 = worn
+        .iter()
+        .flat_map(|(_label, item)| item)
+        .filter_map(|item| power_bonus.get(*item))
+        .map(|bonus| bonus.power)
+        .sum();
+    let equipment_defense: i32 = worn
+        .iter()
+        .flat_map(|(_label, item)| item)
+        .filter_map(|item| defense_bonus.get(*item))
+        .map(|bonus| bonus.defense)
+        .sum();
+    let hunger = ecs
+        .read_storage::<HungerClock>()
+        .get(player_entity)
+        .map(|clock| clock.state);
+    // Same bonus the melee system hands out
+    let well_fed_bonus = if hunger == Some(WellFed) { 1 } else { 0 };
+
+    y += 2;
+    ctx.print_color(6, y, yellow(), black(), "Attributes");
+    if let Some(stats) = ecs.read_storage::<CombatStats>().get(player_entity) {
+        sheet_line(
+            ctx,
+            y + 1,
+            "Hit points",
+            format!("{}/{}", stats.hp, stats.max_hp),
+        );
+        sheet_line(ctx, y + 3, "Strength", stats.strength.to_string());
+        sheet_line(ctx, y + 4, "Defense", stats.defense.to_string());
+        sheet_line(
+            ctx,
+            y + 9,
+            "Melee power",
+            format!(
+                "{} ({} {:+} gear {:+} food)",
+                stats.strength + equipment_power + well_fed_bonus,
+                stats.strength,
+                equipment_power,
+                well_fed_bonus
+            ),
+        );
+        sheet_line(
+            ctx,
+            y + 10,
+            "Defense",
+            format!(
+                "{} ({} {:+} gear)",
+                stats.defense + equipment_defense,
+                stats.defense,
+                equipment_defense
+            ),
+        );
+        sheet_line(
+            ctx,
+            y + 11,
+            "Carrying",
+            format!(
+                "{:.1}/{:.0} lbs",
+                carried_weight(ecs, player_entity),
+                carry_capacity(stats)
+            ),
+        );
+    }
+    if let Some(pool) = ecs.read_storage::<ManaPool>().get(player_entity) {
+        sheet_line(
+            ctx,
+            y + 2,
+            "Mana",
+            format!("{}/{}", pool.mana, pool.max_mana),
+        );
+    }
+    if let Some(viewshed) = ecs.read_storage::<Viewshed>().get(player_entity) {
+        sheet_line(ctx, y + 5, "Sight", viewshed.range.to_string());
+    }
+
+    y += 8;
+    ctx.print_color(6, y, yellow(), black(), "Combat");
+
+    y += 6;
+    ctx.print_color(6, y, yellow(), black(), "Status");
+    let mut statuses: Vec<(String, RGB)> = Vec::new();
+    match hunger {
+        Some(WellFed) => statuses.push(("Well fed (+1 power)".to_string(), green())),
+        Some(Hungry) => statuses.push(("Hungry".to_string(), orange())),
+        Some(Starving) => statuses.push(("Starving".to_string(), red())),
+        _ => {}
+    }
+    if let Some(confusion) = ecs.read_storage::<Confusion>().get(player_entity) {
+        statuses.push((format!("Confused ({} turns)", confusion.turns), magenta()));
+    }
+    match encumbrance(ecs, player_entity) {
+        Encumbrance::Unburdened => {}
+        Encumbrance::Burdened => statuses.push(("Burdened".to_string(), orange())),
+        Encumbrance::Overloaded => statuses.push(("Overloaded".to_string(), red())),
+    }
+    if artifact_recovered(ecs) {
+        statuses.push(("Carrying the Heart of the Mountain".to_string(), yellow()));
+    }
+    if statuses.is_empty() {
+        statuses.push(("Nothing out of the ordinary".to_string(), grey()));
+    }
+    for (i, (status, color)) in statuses.iter().enumerate() {
+        ctx.print_color(8, y + 1 + i as i32, *color, black(), status);
+    }
+
+    y += statuses.len() as i32 + 2;
+    ctx.print_color(6, y, yellow(), black(), "Progress");
+    let depth = if map.depth == 0 {
+        "Town".to_string()
+    } else {
+        map.depth.to_string()
+    };
+    if let Some(experience) = ecs.read_storage::<Experience>().get(player_entity) {
+        sheet_line(ctx, y + 1, "Level", experience.level.to_string());
+        sheet_line(
+            ctx,
+            y + 2,
+            "Experience",
+            format!(
+                "{}/{} to level {}",
+                experience.xp,
+                xp_to_next_level(experience.level),
+                experience.level + 1
+            ),
+        );
+    }
+    sheet_line(ctx, y + 3, "Depth", depth);
+    sheet_line(ctx, y + 4, "Deepest", run_stats.deepest_depth.to_string());
+    sheet_line(ctx, y + 5, "Turns", run_stats.turns.to_string());
+    sheet_line(ctx, y + 6, "Kills", run_stats.total_kills().to_string());
+    sheet_line(
+        ctx,
+        y + 7,
+        "Gold",
+        trade::gold(ecs, player_entity).to_string(),
+    );
+
+    ctx.print_color(40, 6, yellow(), black(), "Equipment");
+    for (i, (label, item)) in worn.iter().enumerate() {
+        let y = 7 + i as i32 * 2;
+        ctx.print_color(42, y, grey(), black(), format!("{}:", label));
+        match item {
+            Some(item) => {
+                ctx.print(54, y, item_display_name(ecs, *item));
+                let mut bonuses = Vec::new();
+                if let Some(bonus) = power_bonus.get(*item) {
+                    bonuses.push(format!("{:+} power", bonus.power));
+                }
+                if let Some(bonus) = defense_bonus.get(*item) {
+                    bonuses.push(format!("{:+} defense", bonus.defense));
+                }
+                ctx.print_color(56, y + 1, cyan(), black(), bonuses.join(", "));
+            }
+            None => ctx.print_color(54, y, grey(), black(), "-"),
+        }
+    }
+
+    match ctx.key {
+        Some(VirtualKeyCode::Escape) => ItemMenuResult::Cancel,
+        _ => ItemMenuResult::NoResponse,
+    }
+}
+
+fn sheet_line(ctx: &mut Rltk, y: i32, label: &str, value: String) {
+    ctx.print_color(8, y, grey(), black(), format!("{}:", label));
+    ctx.print_color(22, y, white(), black(), value);
+}
+
+const OPTION_COUNT: usize = 6;
+
+/// The options screen. Left and right (or Enter) change the highlighted setting.
+pub fn options_menu(ecs: &World, ctx: &mut Rltk, selected: usize) -> OptionsResult {
+    let mut settings = ecs.fetch_mut::<Settings>();
+    ctx.draw_box(15, 12, 49, 18, white(), black());
+    ctx.print_color(18, 12, yellow(), black(), "Options");
+
+    let on_off = |on: bool| if on { "On" } else { "Off" }.to_string();
+    let options: [(&str, String); OPTION_COUNT] = [
+        ("Fullscreen", on_off(settings.fullscreen)),
+        (
+            "Font",
+            match settings.font {
+                Font::Terminal8x8 => "Terminal 8x8".to_string(),
+                Font::Vga8x16 => "VGA 8x16".to_string(),
+            },
+        ),
+        ("Map generation", on_off(settings.show_mapgen_visualizer)),
+        (
+            "Map generation speed",
+            format!("{} ms per step", settings.mapgen_step_ms),
+        ),
+        ("Tooltip delay", format!("{} ms", settings.tooltip_delay_ms)),
+        ("Log length", format!("{} messages", settings.log_length)),
+    ];
+    for (i, (label, value)) in options.iter().enumerate() {
+        let y = 14 + i as i32 * 2;
+        let fg = if i == selected { magenta() } else { white() };
+        ctx.print_color(18, y, fg, black(), label);
+        ctx.print_color(41, y, fg, black(), format!("< {} >", value));
+    }
+    ctx.print_color(
+        18,
+        27,
+        grey(),
+        black(),
+        "Fullscreen and font apply after a restart.",
+    );
+    ctx.print_color(18, 30, yellow(), black(), "Escape to save and close");
+
+    let step = match ctx.key {
+        None => return OptionsResult::NoSelection { selected },
+        Some(VirtualKeyCode::Escape) => return OptionsResult::Close,
+        Some(VirtualKeyCode::Up) | Some(VirtualKeyCode::W) => {
+            return OptionsResult::NoSelection {
+                selected: (selected + OPTION_COUNT - 1) % OPTION_COUNT,
+            }
+        }
+        Some(VirtualKeyCode::Down) | Some(VirtualKeyCode::S) => {
+            return OptionsResult::NoSelection {
+                selected: (selected + 1) % OPTION_COUNT,
+            }
+        }
+        Some(VirtualKeyCode::Left) | Some(VirtualKeyCode::A) => -1,
+        Some(VirtualKeyCode::Right)
+        | Some(VirtualKeyCode::D)
+        | Some(VirtualKeyCode::Return)
+        | Some(VirtualKeyCode::Space) => 1,
+        Some(_) => return OptionsResult::NoSelection { selected },
+    };
+    match selected {
+        0 => settings.fullscreen = !settings.fullscreen,
+        1 => settings.font = cycle(&[Font::Terminal8x8, Font::Vga8x16], settings.font, step),
+        2 => settings.show_mapgen_visualizer = !settings.show_mapgen_visualizer,
+        3 => settings.mapgen_step_ms = cycle(&MAPGEN_SPEEDS_MS, settings.mapgen_step_ms, step),
+        4 => settings.tooltip_delay_ms = cycle(&TOOLTIP_DELAYS_MS, settings.tooltip_delay_ms, step),
+        _ => settings.log_length = cycle(&LOG_LENGTHS, settings.log_length, step),
     }
+    drop(settings);
+    apply_settings(ecs);
+    OptionsResult::NoSelection { selected }
 }
 
-pub fn game_over(ctx: &mut Rltk) -> GameOverResult {
+/// Every command and the keys it's on, straight from the active key bindings.
+pub fn help_screen(ecs: &World, ctx: &mut Rltk) -> ItemMenuResult {
+    let keymap = ecs.fetch::<Keymap>();
+    ctx.draw_box(2, 2, 75, 40, white(), black());
+    ctx.print_color(5, 2, yellow(), black(), "Help");
+    ctx.print_color(
+        5,
+        3,
+        grey(),
+        black(),
+        format!(
+            "Key bindings: {:?} preset. Change them in keybindings.json.",
+            keymap.preset
+        ),
+    );
+
+    let commands = Command::all();
+    let per_column = commands.len().div_ceil(2);
+    for (i, command) in commands.iter().enumerate() {
+        let x = if i < per_column { 5 } else { 42 };
+        let y = 5 + (i % per_column) as i32;
+        let keys: Vec<String> = keymap
+            .keys_for(*command)
+            .iter()
+            .map(|key| key_name(*key))
+            .collect();
+        ctx.print_color(x, y, white(), black(), command.description());
+        let keys = if keys.is_empty() {
+            "-".to_string()
+        } else {
+            keys.join(", ")
+        };
+        ctx.print_color(x + 17, y, cyan(), black(), keys);
+    }
+
+    let mut y = 7 + per_column as i32;
+    if !keymap.problems.is_empty() {
+        ctx.print_color(5, y, red(), black(), "Problems with your key bindings:");
+        for problem in keymap.problems.iter() {
+            y += 1;
+            for line in wrap(problem, 68) {
+                ctx.print_color(7, y, orange(), black(), line);
+                y += 1;
+            }
+        }
+    }
+    ctx.print_color(5, 42, yellow(), black(), "Escape to close");
+
+    match ctx.key {
+        Some(VirtualKeyCode::Escape) => ItemMenuResult::Cancel,
+        _ => ItemMenuResult::NoResponse,
+    }
+}
+
+/// Back in town with the artifact: the run is won.
+pub fn victory(ecs: &World, ctx: &mut Rltk) -> GameOverResult {
+    ctx.print_color_centered(15, yellow(), black(), "VICTORY");
+    ctx.print_color_centered(
+        17,
+        white(),
+        black(),
+        "You made it back to the surface with the Heart of the Mountain!",
+    );
+    draw_run_stats(ecs, ctx, 20);
+
+    ctx.print_color_centered(
+        43,
+        magenta(),
+        black(),
+        "Press Enter key to return to the Main Menu.",
+    );
+    match ctx.key {
+        Some(VirtualKeyCode::Return) => GameOverResult::QuitToMenu,
+        _ => GameOverResult::NoSelection,
+    }
+}
+
+pub fn game_over(ecs: &World, ctx: &mut Rltk) -> GameOverResult {
     ctx.print_color_centered(15, yellow(), black(), "GAME OVER");
+    if let Some(cause) = &ecs.fetch::<RunStats>().cause_of_death {
+        ctx.print_color_centered(17, red(), black(), format!("Killed by {}.", cause));
+    }
+    draw_run_stats(ecs, ctx, 20);
+
     ctx.print_color_centered(
         40,
         white(),
@@ -473,10 +1347,291 @@ pub fn game_over(ctx: &mut Rltk) -> GameOverResult {
         "Press Enter key to return to the Main Menu.",
     );
     match ctx.key {
-        None => GameOverResult::NoSelection,
-        Some(key) => match key {
-            VirtualKeyCode::Return => GameOverResult::QuitToMenu,
-            _ => GameOverResult::NoSelection,
+        Some(VirtualKeyCode::Return) => GameOverResult::QuitToMenu,
+        _ => GameOverResult::NoSelection,
+    }
+}
+
+/// The numbers of the run that just ended, starting at row `y`. Kills are broken down per
+/// monster type, most killed first.
+fn draw_run_stats(ecs: &World, ctx: &mut Rltk, y: i32) {
+    let stats = ecs.fetch::<RunStats>();
+    let lines = [
+        format!("Score: {}", current_score(ecs)),
+        format!("Turns taken: {}", stats.turns),
+        format!("Deepest depth: {}", stats.deepest_depth),
+        format!(
+            "Damage dealt: {}  Damage taken: {}",
+            stats.damage_dealt, stats.damage_taken
+        ),
+        format!("Items used: {}", stats.items_used),
+        format!("Monsters killed: {}", stats.total_kills()),
+    ];
+    for (i, line) in lines.iter().enumerate() {
+        ctx.print_color_centered(y + i as i32, white(), black(), line);
+    }
+
+    let mut kills: Vec<(&String, &i32)> = stats.kills.iter().collect();
+    kills.sort_by(|a, b| b.1.cmp(a.1));
+    for (i, (name, count)) in kills.iter().take(8).enumerate() {
+        ctx.print_color_centered(
+            y + lines.len() as i32 + i as i32,
+            grey(),
+            black(),
+            format!("{} x{}", name, count),
+        );
+    }
+}
+
+/// The best runs so far, read from disk. Escape or Enter goes back to the main menu.
+pub fn high_score_table(ctx: &mut Rltk) -> ItemMenuResult {
+    let high_scores = load_high_scores();
+    ctx.draw_box(5, 10, 69, 16, white(), black());
+    ctx.print_color_centered(10, yellow(), black(), "High Scores");
+    if high_scores.is_empty() {
+        ctx.print_color_centered(13, grey(), black(), "No runs yet. Go make some history!");
+    }
+    for (i, entry) in high_scores.iter().enumerate() {
+        let y = 12 + i as i32;
+        ctx.print_color(7, y, yellow(), black(), format!("{:>2}.", i + 1));
+        ctx.print_color(11, y, white(), black(), format!("{:>6}", entry.score));
+        ctx.print_color(
+            19,
+            y,
+            grey(),
+            black(),
+            format!("{} ({} turns)", entry.fate, entry.turns),
+        );
+    }
+    ctx.print_color_centered(26, magenta(), black(), "Press Escape to go back.");
+
+    match ctx.key {
+        Some(VirtualKeyCode::Escape) | Some(VirtualKeyCode::Return) => ItemMenuResult::Cancel,
+        _ => ItemMenuResult::NoResponse,
+    }
+}
+
+/// Prints a log line fragment by fragment, cut off at `max_width` characters.
+fn draw_log_entry(ctx: &mut Rltk, x: i32, y: i32, entry: &LogEntry, max_width: usize) {
+    let mut x = x;
+    let mut room = max_width;
+    for fragment in entry.fragments.iter() {
+        if room == 0 {
+            break;
+        }
+        let text: String = fragment.text.chars().take(room).collect();
+        let length = text.chars().count();
+        ctx.print_color(x, y, fragment.color, black(), text);
+        x += length as i32;
+        room -= length;
+    }
+}
+
+const LOG_VIEWER_LINES: usize = 42;
+
+/// The whole log, scrollable and searchable.
+pub fn log_viewer(ecs: &World, ctx: &mut Rltk) -> ItemMenuResult {
+    let log = ecs.fetch::<GameLog>();
+    let mut viewer = ecs.fetch_mut::<LogViewer>();
+    let matching: Vec<&LogEntry> = log
+        .entries
+        .iter()
+        .filter(|entry| viewer.matches(entry))
+        .collect();
+
+    let max_scroll = matching.len().saturating_sub(LOG_VIEWER_LINES);
+    viewer.scroll = viewer.scroll.min(max_scroll);
+    let last = matching.len() - viewer.scroll;
+    let first = last.saturating_sub(LOG_VIEWER_LINES);
+
+    ctx.draw_box(0, 0, 79, 49, white(), black());
+    ctx.print_color_centered(0, yellow(), black(), "Message Log");
+    for (y, entry) in (2..).zip(matching[first..last].iter()) {
+        ctx.print_color(2, y, grey(), black(), format!("[{:>5}]", entry.turn));
+        draw_log_entry(ctx, 10, y, entry, 68);
+    }
+    if matching.is_empty() {
+        ctx.print_color_centered(20, grey(), black(), "No messages match.");
+    }
+
+    let search_color = if viewer.typing { yellow() } else { grey() };
+    let cursor = if viewer.typing { "_" } else { "" };
+    ctx.print_color(
+        2,
+        46,
+        search_color,
+        black(),
+        format!("Search: {}{}", viewer.query, cursor),
+    );
+    ctx.print_color(
+        2,
+        47,
+        magenta(),
+        black(),
+        format!(
+            "Lines {}-{} of {}",
+            (first + 1).min(last),
+            last,
+            matching.len()
+        ),
+    );
+    if viewer.typing {
+        ctx.print_color_centered(49, magenta(), black(), "Type to search, Enter to finish");
+    } else {
+        ctx.print_color_centered(
+            49,
+            magenta(),
+            black(),
+            "Up/Down/PgUp/PgDn/Home/End to scroll, / to search, Escape to close",
+        );
+    }
+
+    if viewer.typing {
+        match ctx.key {
+            Some(VirtualKeyCode::Escape) | Some(VirtualKeyCode::Return) => viewer.typing = false,
+            Some(VirtualKeyCode::Back) => {
+                viewer.query.pop();
+                viewer.scroll = 0;
+            }
+            Some(key) => {
+                if let Some(c) = typed_char(key) {
+                    viewer.query.push(c);
+                    viewer.scroll = 0;
+                }
+            }
+            None => {}
+        }
+        return ItemMenuResult::NoResponse;
+    }
+
+    match ctx.key {
+        Some(VirtualKeyCode::Escape) => return ItemMenuResult::Cancel,
+        Some(VirtualKeyCode::Up) => viewer.scroll = (viewer.scroll + 1).min(max_scroll),
+        Some(VirtualKeyCode::Down) => viewer.scroll = viewer.scroll.saturating_sub(1),
+        Some(VirtualKeyCode::PageUp) => {
+            viewer.scroll = (viewer.scroll + LOG_VIEWER_LINES).min(max_scroll)
+        }
+        Some(VirtualKeyCode::PageDown) => {
+            viewer.scroll = viewer.scroll.saturating_sub(LOG_VIEWER_LINES)
+        }
+        Some(VirtualKeyCode::Home) => viewer.scroll = max_scroll,
+        Some(VirtualKeyCode::End) => viewer.scroll = 0,
+        Some(VirtualKeyCode::Slash) => viewer.typing = true,
+        _ => {}
+    }
+    ItemMenuResult::NoResponse
+}
+
+/// One step of the character creation screens: pick a race, pick a class, type a name.
+pub fn character_creation(
+    ecs: &World,
+    ctx: &mut Rltk,
+    step: CreationStep,
+    selected: usize,
+) -> CreationResult {
+    let mut draft = ecs.fetch_mut::<CharacterDraft>();
+    ctx.draw_box_double(10, 12, 59, 22, grey(), black());
+    ctx.print_color_centered(12, yellow(), black(), "Create your character");
+
+    let options: Vec<(&str, &str)> = match step {
+        CreationStep::Race => RACES
+            .iter()
+            .map(|race| (race_name(*race), race_description(*race)))
+            .collect(),
+        CreationStep::Class => CLASSES
+            .iter()
+            .map(|class| (class_name(*class), class_description(*class)))
+            .collect(),
+        CreationStep::Name => Vec::new(),
+    };
+
+    match step {
+        CreationStep::Race => ctx.print_color(13, 14, white(), black(), "Choose your race:"),
+        CreationStep::Class => ctx.print_color(
+            13,
+            14,
+            white(),
+            black(),
+            format!("{}. Choose your class:", race_name(draft.race)),
+        ),
+        CreationStep::Name => {
+            ctx.print_color(
+                13,
+                14,
+                white(),
+                black(),
+                format!(
+                    "{} {}. What is your name?",
+                    race_name(draft.race),
+                    class_name(draft.class)
+                ),
+            );
+            ctx.print_color(15, 17, yellow(), black(), format!("{}_", draft.name));
+        }
+    }
+    for (i, (name, description)) in options.iter().enumerate() {
+        let y = 16 + i as i32 * 3;
+        let fg = if i == selected { magenta() } else { white() };
+        ctx.print_color(15, y, fg, black(), name);
+        ctx.print_color(17, y + 1, grey(), black(), description);
+    }
+    let help = match step {
+        CreationStep::Name => "Type a name, Enter to begin, Escape to go back",
+        _ => "Up/Down to choose, Enter to select, Escape to go back",
+    };
+    ctx.print_color_centered(33, magenta(), black(), help);
+
+    let key = match ctx.key {
+        None => return CreationResult::NoSelection { selected },
+        Some(key) => key,
+    };
+    match key {
+        VirtualKeyCode::Escape => CreationResult::Back,
+        VirtualKeyCode::Return => CreationResult::Selected { selected },
+        _ if step == CreationStep::Name => {
+            if key == VirtualKeyCode::Back {
+                draft.name.pop();
+            } else if let Some(c) = typed_char(key) {
+                if draft.name.len() < MAX_NAME_LENGTH {
+                    // Names start with a capital
+                    if draft.name.is_empty() || draft.name.ends_with(' ') {
+                        draft.name.extend(c.to_uppercase());
+                    } else {
+                        draft.name.push(c);
+                    }
+                }
+            }
+            CreationResult::NoSelection { selected }
+        }
+        VirtualKeyCode::Up | VirtualKeyCode::W => CreationResult::NoSelection {
+            selected: (selected + options.len() - 1) % options.len(),
         },
+        VirtualKeyCode::Down | VirtualKeyCode::S => CreationResult::NoSelection {
+            selected: (selected + 1) % options.len(),
+        },
+        _ => CreationResult::NoSelection { selected },
+    }
+}
+
+/// The character a key stands for when typing a search query or a name, if any.
+fn typed_char(key: VirtualKeyCode) -> Option<char> {
+    let letter = rltk::letter_to_option(key);
+    if letter >= 0 {
+        return Some((b'a' + letter as u8) as char);
+    }
+    match key {
+        VirtualKeyCode::Key0 => Some('0'),
+        VirtualKeyCode::Key1 => Some('1'),
+        VirtualKeyCode::Key2 => Some('2'),
+        VirtualKeyCode::Key3 => Some('3'),
+        VirtualKeyCode::Key4 => Some('4'),
+        VirtualKeyCode::Key5 => Some('5'),
+        VirtualKeyCode::Key6 => Some('6'),
+        VirtualKeyCode::Key7 => Some('7'),
+        VirtualKeyCode::Key8 => Some('8'),
+        VirtualKeyCode::Key9 => Some('9'),
+        VirtualKeyCode::Space => Some(' '),
+        VirtualKeyCode::Apostrophe => Some('\''),
+        _ => None,
     }
 }
@@ -1,6 +1,6 @@
 extern crate specs;
-use crate::components::SufferDamage;
 use crate::components::{HungerClock, HungerState::*};
+use crate::effects::{EffectQueue, EffectType, Targets};
 use crate::gamelog::GameLog;
 use crate::RunState;
 use specs::prelude::*;
@@ -14,15 +14,14 @@ impl<'a> System<'a> for HungerSystem {
         WriteStorage<'a, HungerClock>,
         ReadExpect<'a, Entity>, // The player
         ReadExpect<'a, RunState>,
-        WriteStorage<'a, SufferDamage>,
+        WriteExpect<'a, EffectQueue>,
         WriteExpect<'a, GameLog>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut hunger_clock, player_entity, runstate, mut inflict_damage, mut log) =
-            data;
+        let (entities, mut hunger_clock, player_entity, runstate, mut effects, mut log) = data;
 
-        for (entity, mut clock) in (&entities, &mut hunger_clock).join() {
+        for (entity, clock) in (&entities, &mut hunger_clock).join() {
             let mut proceed = false;
 
             match *runstate {
@@ -46,34 +45,36 @@ impl<'a> System<'a> for HungerSystem {
                             clock.state = Normal;
                             clock.duration = 200;
                             if entity == *player_entity {
-                                log.entries.push("You no longer feel well fed.".to_string());
+                                log.add("You no longer feel well fed.".to_string());
                             }
                         }
                         Normal => {
                             clock.state = Hungry;
                             clock.duration = 200;
                             if entity == *player_entity {
-                                log.entries.push("You feel hungry.".to_string());
+                                log.add("You feel hungry.".to_string());
                             }
                         }
                         Hungry => {
                             clock.state = Starving;
                             clock.duration = 200;
                             if entity == *player_entity {
-                                log.entries.push("You are starving.".to_string());
+                                log.add("You are starving.".to_string());
                             }
                         }
                         Starving => {
                             if entity == *player_entity {
-                                log.entries.push(format!(
+                                log.add(format!(
                                     "You lose {} health from starvation.",
                                     STARVATION_DAMAGE
                                 ));
-                                SufferDamage::new_damage(
-                                    &mut inflict_damage,
-                                    entity,
-                                    STARVATION_DAMAGE,
-                                )
+                                effects.add_effect(
+                                    None,
+                                    EffectType::Damage {
+                                        amount: STARVATION_DAMAGE,
+                                    },
+                                    Targets::Single { target: entity },
+                                );
                             }
                         }
                     }
@@ -0,0 +1,157 @@
+use super::{gamelog::GameLog, Name, ObfuscatedName};
+use rltk::RandomNumberGenerator;
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+const SCROLL_SYLLABLES: [&str; 16] = [
+    "ZEL", "GO", "MER", "FOO", "BAR", "XIX", "ELB", "ERE", "KIR", "JE", "LAM", "PRAT", "YUM",
+    "ABRA", "NR", "VE",
+];
+
+const POTION_ADJECTIVES: [&str; 12] = [
+    "murky",
+    "bubbling",
+    "fizzy",
+    "swirly",
+    "smoky",
+    "cloudy",
+    "glowing",
+    "oily",
+    "viscous",
+    "sparkling",
+    "milky",
+    "effervescent",
+];
+
+/// Per-run mapping from the real names of scrolls and potions to the names
+/// the player sees until they are identified. Lives in the save game.
+#[derive(Default, Serialize, Deserialize, Clone)]
+pub struct MagicItemNames {
+    scroll_names: HashMap<String, String>,
+    potion_names: HashMap<String, String>,
+    identified: HashSet<String>,
+}
+
+impl MagicItemNames {
+    pub fn new() -> MagicItemNames {
+        MagicItemNames::default()
+    }
+
+    pub fn scroll_name(&mut self, real_name: &str, rng: &mut RandomNumberGenerator) -> String {
+        if let Some(name) = self.scroll_names.get(real_name) {
+            return name.clone();
+        }
+        let mut name = random_scroll_name(rng);
+        while self.scroll_names.values().any(|n| *n == name) {
+            name = random_scroll_name(rng);
+        }
+        self.scroll_names
+            .insert(real_name.to_string(), name.clone());
+        name
+    }
+
+    pub fn potion_name(&mut self, real_name: &str, rng: &mut RandomNumberGenerator) -> String {
+        if let Some(name) = self.potion_names.get(real_name) {
+            return name.clone();
+        }
+        let unused: Vec<&str> = POTION_ADJECTIVES
+            .iter()
+            .filter(|adjective| {
+                !self
+                    .potion_names
+                    .values()
+                    .any(|n| *n == format!("{} potion", adjective))
+            })
+            .copied()
+            .collect();
+        // Once every adjective is taken, combine two of them
+        let adjective = if unused.is_empty() {
+            format!(
+                "{} {}",
+                POTION_ADJECTIVES[rng.roll_dice(1, POTION_ADJECTIVES.len() as i32) as usize - 1],
+                POTION_ADJECTIVES[rng.roll_dice(1, POTION_ADJECTIVES.len() as i32) as usize - 1]
+            )
+        } else {
+            unused[rng.roll_dice(1, unused.len() as i32) as usize - 1].to_string()
+        };
+        let name = format!("{} potion", adjective);
+        self.potion_names
+            .insert(real_name.to_string(), name.clone());
+        name
+    }
+
+    pub fn is_identified(&self, real_name: &str) -> bool {
+        self.identified.contains(real_name)
+    }
+
+    /// Returns true if the item was not known before.
+    pub fn identify(&mut self, real_name: &str) -> bool {
+        self.identified.insert(real_name.to_string())
+    }
+}
+
+fn random_scroll_name(rng: &mut RandomNumberGenerator) -> String {
+    let words = rng.roll_dice(1, 2);
+    let mut name = "scroll of".to_string();
+    for _word in 0..words {
+        name += " ";
+        for _syllable in 0..rng.roll_dice(1, 2) {
+            name += SCROLL_SYLLABLES[rng.roll_dice(1, SCROLL_SYLLABLES.len() as i32) as usize - 1];
+        }
+    }
+    name
+}
+
+/// The name the player knows `item` by: the real one, or the obfuscated one
+/// for magic items that haven't been identified yet.
+pub fn display_name(
+    item: Entity,
+    names: &ReadStorage<Name>,
+    obfuscated: &ReadStorage<ObfuscatedName>,
+    magic_item_names: &MagicItemNames,
+) -> String {
+    match names.get(item) {
+        None => "unknown item".to_string(),
+        Some(name) => match obfuscated.get(item) {
+            Some(obfuscated) if !magic_item_names.is_identified(&name.name) => {
+                obfuscated.name.clone()
+            }
+            _ => name.name.clone(),
+        },
+    }
+}
+
+pub fn item_display_name(ecs: &World, item: Entity) -> String {
+    display_name(
+        item,
+        &ecs.read_storage::<Name>(),
+        &ecs.read_storage::<ObfuscatedName>(),
+        &ecs.fetch::<MagicItemNames>(),
+    )
+}
+
+pub fn is_unidentified(ecs: &World, item: Entity) -> bool {
+    let names = ecs.read_storage::<Name>();
+    let obfuscated = ecs.read_storage::<ObfuscatedName>();
+    let magic_item_names = ecs.fetch::<MagicItemNames>();
+    match (names.get(item), obfuscated.get(item)) {
+        (Some(name), Some(_)) => !magic_item_names.is_identified(&name.name),
+        _ => false,
+    }
+}
+
+/// Learns the true name of `item` (and every other item of its kind).
+pub fn identify_item(ecs: &mut World, item: Entity) {
+    if !is_unidentified(ecs, item) {
+        return;
+    }
+    let unknown_name = item_display_name(ecs, item);
+    let real_name = match ecs.read_storage::<Name>().get(item) {
+        Some(name) => name.name.clone(),
+        None => return,
+    };
+    ecs.fetch_mut::<MagicItemNames>().identify(&real_name);
+    ecs.fetch_mut::<GameLog>()
+        .add(format!("The {} is a {}.", unknown_name, real_name));
+}
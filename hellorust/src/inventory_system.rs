@@ -1,12 +1,16 @@
 extern crate specs;
 use super::{
-    gamelog::GameLog, AreaOfEffect, CombatStats, Confusion, Consumable, Destructable, Equippable,
-    Equipped, HungerClock, HungerState, InBackpack, InflictsDamage, MagicMapper, Map, Name,
-    ParticleBuilder, Position, ProvidesFood, ProvidesHealing, RunState, SufferDamage,
-    WantsToDropItem, WantsToPickupItem, WantsToRemoveEquipment, WantsToUseItem,
+    effects::{EffectQueue, EffectType, Targets},
+    gamelog::GameLog,
+    identification::{display_name, MagicItemNames},
+    AreaOfEffect, Artifact, CursedItem, EquipmentSlot, Equippable, Equipped, GoldPile, InBackpack,
+    Map, Name, ObfuscatedName, Position, Wallet, WantsToDropItem, WantsToPickupItem,
+    WantsToRemoveEquipment, WantsToUseItem,
 };
-use crate::color::*;
+use crate::statistics::RunStats;
 use specs::prelude::*;
+use specs::storage::MaskedStorage;
+use std::ops::Deref;
 
 pub struct ItemCollectionSystem {}
 
@@ -17,15 +21,48 @@ impl<'a> System<'a> for ItemCollectionSystem {
         WriteStorage<'a, WantsToPickupItem>,
         WriteStorage<'a, Position>,
         ReadStorage<'a, Name>,
+        ReadStorage<'a, ObfuscatedName>,
+        ReadExpect<'a, MagicItemNames>,
         WriteStorage<'a, InBackpack>,
+        Entities<'a>,
+        ReadStorage<'a, GoldPile>,
+        WriteStorage<'a, Wallet>,
+        ReadStorage<'a, Artifact>,
+        WriteExpect<'a, RunStats>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (player_entity, mut gamelog, mut wants_pickup, mut positions, names, mut backpack) =
-            data;
+        let (
+            player_entity,
+            mut gamelog,
+            mut wants_pickup,
+            mut positions,
+            names,
+            obfuscated_names,
+            magic_item_names,
+            mut backpack,
+            entities,
+            gold_piles,
+            mut wallets,
+            artifacts,
+            mut run_stats,
+        ) = data;
 
         for pickup in wants_pickup.join() {
             positions.remove(pickup.item);
+
+            // Gold goes straight into the wallet instead of the backpack
+            if let Some(gold_pile) = gold_piles.get(pickup.item) {
+                if let Some(wallet) = wallets.get_mut(pickup.collected_by) {
+                    wallet.gold += gold_pile.amount;
+                    if pickup.collected_by == *player_entity {
+                        gamelog.add(format!("You pick up {} gold.", gold_pile.amount));
+                    }
+                    entities.delete(pickup.item).expect("Unable to delete gold");
+                    continue;
+                }
+            }
+
             backpack
                 .insert(
                     pickup.item,
@@ -36,10 +73,16 @@ impl<'a> System<'a> for ItemCollectionSystem {
                 .expect("Unable to insert backpack entry");
 
             if pickup.collected_by == *player_entity {
-                gamelog.entries.push(format!(
+                gamelog.add(format!(
                     "You pick up the {}.",
-                    names.get(pickup.item).unwrap().name
+                    display_name(pickup.item, &names, &obfuscated_names, &magic_item_names)
                 ));
+                if artifacts.get(pickup.item).is_some() {
+                    run_stats.artifact_recovered = true;
+                    gamelog.add(
+                        "The dungeon trembles. Now bring it back up to the surface!".to_string(),
+                    );
+                }
             }
         }
         wants_pickup.clear();
@@ -50,16 +93,39 @@ pub struct EquipmentRemoveSystem {}
 
 impl<'a> System<'a> for EquipmentRemoveSystem {
     type SystemData = (
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, GameLog>,
         Entities<'a>,
         WriteStorage<'a, WantsToRemoveEquipment>,
         WriteStorage<'a, Equipped>,
         WriteStorage<'a, InBackpack>,
+        ReadStorage<'a, CursedItem>,
+        ReadStorage<'a, Name>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut wants_to_remove, mut equipped, mut backpack) = data;
+        let (
+            player_entity,
+            mut gamelog,
+            entities,
+            mut wants_to_remove,
+            mut equipped,
+            mut backpack,
+            cursed,
+            names,
+        ) = data;
 
         for (entity, to_remove) in (&entities, &wants_to_remove).join() {
+            // Cursed items stay on until the curse is lifted
+            if cursed.get(to_remove.item).is_some() {
+                if entity == *player_entity {
+                    gamelog.add(format!(
+                        "You can't remove the {}, it is cursed!",
+                        names.get(to_remove.item).unwrap().name
+                    ));
+                }
+                continue;
+            }
             equipped.remove(to_remove.item);
             backpack
                 .insert(to_remove.item, InBackpack { owner: entity })
@@ -79,8 +145,11 @@ impl<'a> System<'a> for ItemDropSystem {
         Entities<'a>,
         WriteStorage<'a, WantsToDropItem>,
         ReadStorage<'a, Name>,
+        ReadStorage<'a, ObfuscatedName>,
+        ReadExpect<'a, MagicItemNames>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, InBackpack>,
+        ReadStorage<'a, Artifact>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -90,11 +159,25 @@ impl<'a> System<'a> for ItemDropSystem {
             entities,
             mut wants_drop,
             names,
+            obfuscated_names,
+            magic_item_names,
             mut positions,
             mut backpack,
+            artifacts,
         ) = data;
 
         for (entity, to_drop) in (&entities, &wants_drop).join() {
+            // The artifact stays with whoever found it until the run is over
+            if artifacts.get(to_drop.item).is_some() {
+                if entity == *player_entity {
+                    gamelog.add(format!(
+                        "You can't bring yourself to leave the {} behind.",
+                        display_name(to_drop.item, &names, &obfuscated_names, &magic_item_names)
+                    ));
+                }
+                continue;
+            }
+
             let mut dropper_pos: Position = Position { x: 0, y: 0 };
             {
                 // set the drop position to the position of the dropper (could be player could be monster)
@@ -114,9 +197,9 @@ impl<'a> System<'a> for ItemDropSystem {
             backpack.remove(to_drop.item);
 
             if entity == *player_entity {
-                gamelog.entries.push(format!(
+                gamelog.add(format!(
                     "You drop the {}.",
-                    names.get(to_drop.item).unwrap().name
+                    display_name(to_drop.item, &names, &obfuscated_names, &magic_item_names)
                 ));
             }
         }
@@ -132,27 +215,16 @@ impl<'a> System<'a> for ItemUseSystem {
     type SystemData = (
         ReadExpect<'a, Entity>,
         WriteExpect<'a, GameLog>,
-        WriteExpect<'a, Map>,
+        ReadExpect<'a, Map>,
         Entities<'a>,
         WriteStorage<'a, WantsToUseItem>,
         ReadStorage<'a, Name>,
-        ReadStorage<'a, Consumable>,
-        ReadStorage<'a, ProvidesHealing>,
-        ReadStorage<'a, InflictsDamage>,
         ReadStorage<'a, AreaOfEffect>,
-        WriteStorage<'a, CombatStats>,
-        WriteStorage<'a, SufferDamage>,
-        WriteStorage<'a, Confusion>,
         ReadStorage<'a, Equippable>,
         WriteStorage<'a, Equipped>,
         WriteStorage<'a, InBackpack>,
-        WriteStorage<'a, Destructable>,
-        WriteExpect<'a, ParticleBuilder>,
-        ReadStorage<'a, Position>,
-        ReadStorage<'a, ProvidesFood>,
-        WriteStorage<'a, HungerClock>,
-        ReadStorage<'a, MagicMapper>,
-        WriteExpect<'a, RunState>,
+        ReadStorage<'a, CursedItem>,
+        WriteExpect<'a, EffectQueue>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -163,94 +235,75 @@ impl<'a> System<'a> for ItemUseSystem {
             entities,
             mut wants_use,
             names,
-            consumables,
-            healing,
-            inflicts_damage,
             aoe,
-            mut combat_stats,
-            mut suffer_damage,
-            mut confused,
             equippable,
             mut equipped,
             mut in_backpack,
-            mut destructable,
-            mut particle_builder,
-            positions,
-            provides_food,
-            mut hunger_clocks,
-            magic_mapper,
-            mut runstate,
+            cursed,
+            mut effects,
         ) = data;
 
         for (entity, use_item) in (&entities, &wants_use).join() {
-            let mut used_item = true;
-
             // Targeting
-            let mut targets: Vec<Entity> = Vec::new();
-            match use_item.target {
-                None => {
-                    targets.push(*player_entity);
-                }
-                Some(target) => {
-                    let area_of_effect = aoe.get(use_item.item);
-                    match area_of_effect {
-                        None => {
-                            //single target
-                            let idx = map.xy_idx(target.x, target.y);
-                            for mob in map.tile_content[idx].iter() {
-                                targets.push(*mob);
-                            }
-                        }
-                        Some(area_of_effect) => {
-                            let mut blast_tiles =
-                                rltk::field_of_view(target, area_of_effect.radius, &*map);
-                            // remove out of bounds tiles
-                            blast_tiles.retain(|p| {
-                                p.x > 0 && p.x < map.width - 1 && p.y > 0 && p.y < map.height - 1
-                            });
-                            // loop over remaining tiles...
-                            for tile_idx in blast_tiles.iter() {
-                                let idx = map.xy_idx(tile_idx.x, tile_idx.y);
-                                // ...loop over all entities on a tile
-                                for mob in map.tile_content[idx].iter() {
-                                    targets.push(*mob);
-                                }
-                                particle_builder.request(
-                                    tile_idx.x,
-                                    tile_idx.y,
-                                    orange(),
-                                    black(),
-                                    rltk::to_cp437('░'),
-                                    250.0,
-                                );
-                            }
-                        }
-                    }
-                }
-            }
+            let targets = match use_item.target {
+                None => Targets::Single { target: entity },
+                Some(target) => match aoe.get(use_item.item) {
+                    None => Targets::Tile {
+                        tile_idx: map.xy_idx(target.x, target.y),
+                    },
+                    Some(area_of_effect) => Targets::Area {
+                        target,
+                        radius: area_of_effect.radius,
+                    },
+                },
+            };
 
             // Equipping (replacing active equipped item)
             let item_equippable = equippable.get(use_item.item);
             match item_equippable {
-                None => {}
+                None => {
+                    // Everything else goes through the effects system
+                    effects.add_effect(
+                        Some(entity),
+                        EffectType::ItemUse {
+                            item: use_item.item,
+                        },
+                        targets,
+                    );
+                }
                 Some(can_equip) => {
                     let target_slot = can_equip.slot;
-                    let target = targets[0];
+                    let target = entity;
+                    let in_the_way = equipment_in_the_way(
+                        &entities,
+                        &equipped,
+                        &equippable,
+                        &cursed,
+                        target,
+                        can_equip,
+                    );
+
+                    // A cursed item in the slot can't be swapped out
+                    if let Some(stuck_item) =
+                        in_the_way.iter().find(|item| cursed.get(**item).is_some())
+                    {
+                        if target == *player_entity {
+                            gamelog.add(format!(
+                                "You can't take off the {}, it is cursed!",
+                                names.get(*stuck_item).unwrap().name
+                            ));
+                        }
+                        continue;
+                    }
 
                     // Mark active equipped item as to_unequip
-                    let mut to_unequip: Vec<Entity> = Vec::new();
-                    for (item_entity, currently_equipped, name) in
-                        (&entities, &equipped, &names).join()
-                    {
-                        if currently_equipped.owner == target
-                            && currently_equipped.slot == target_slot
-                        {
-                            to_unequip.push(item_entity);
-                            if target == *player_entity {
-                                gamelog
-                                    .entries
-                                    .push(format!("You unequip the {}.", name.name));
-                            }
+                    let to_unequip = in_the_way;
+                    if target == *player_entity {
+                        for item in to_unequip.iter() {
+                            gamelog.add(format!(
+                                "You unequip the {}.",
+                                names.get(*item).unwrap().name
+                            ));
                         }
                     }
                     // Actually unequip the item
@@ -273,173 +326,155 @@ impl<'a> System<'a> for ItemUseSystem {
                         .expect("Unable to insert equipped component");
                     in_backpack.remove(use_item.item);
                     if target == *player_entity {
-                        gamelog.entries.push(format!(
+                        gamelog.add(format!(
                             "You equip the {}.",
                             names.get(use_item.item).unwrap().name
                         ));
+                        if cursed.get(use_item.item).is_some() {
+                            gamelog.add(format!(
+                                "The {} is cursed! It binds itself to you.",
+                                names.get(use_item.item).unwrap().name
+                            ));
+                        }
                     }
                 }
             }
+        } // big for-loop ends here: (&entities, &wants_use).join
 
-            // Edibles reset hungerclock
-            let item_edible = provides_food.get(use_item.item);
-            match item_edible {
-                None => {}
-                Some(_edible) => {
-                    used_item = true;
-                    let target = targets[0]; // the player
-                    let hunger_clock = hunger_clocks.get_mut(target);
-                    if let Some(hc) = hunger_clock {
-                        hc.state = HungerState::WellFed;
-                        hc.duration = 20;
-                        gamelog.entries.push(format!(
-                            "You eat the {}.",
-                            names.get(use_item.item).unwrap().name
-                        ));
-                    }
-                }
-            }
+        wants_use.clear();
+    }
+}
 
-            // If its a Magic Mapper
-            let is_magic_mapper = magic_mapper.get(use_item.item);
-            match is_magic_mapper {
-                None => {}
-                Some(_) => {
-                    used_item = true;
-                    gamelog.entries.push("All is revealed to you!".to_string());
-                    *runstate = RunState::MagicMapReveal { row: 0 };
-                }
+/// Everything `owner` has to take off before they can equip `new_item`:
+/// whatever is in its slot, the shield when picking up a two-handed weapon
+/// (or the two-handed weapon when picking up a shield), and one of the two
+/// rings when both hands already wear one; a cursed ring is only picked if both are.
+pub fn equipment_in_the_way<D: Deref<Target = MaskedStorage<Equipped>>>(
+    entities: &Entities,
+    equipped: &Storage<Equipped, D>,
+    equippable: &ReadStorage<Equippable>,
+    cursed: &ReadStorage<CursedItem>,
+    owner: Entity,
+    new_item: &Equippable,
+) -> Vec<Entity> {
+    let mut in_the_way = Vec::new();
+    let mut rings = Vec::new();
+    for (item_entity, currently_equipped) in (entities, equipped).join() {
+        if currently_equipped.owner != owner {
+            continue;
+        }
+        let two_handed = equippable
+            .get(item_entity)
+            .is_some_and(|item| item.two_handed);
+        match currently_equipped.slot {
+            EquipmentSlot::Ring if new_item.slot == EquipmentSlot::Ring => rings.push(item_entity),
+            slot if slot == new_item.slot => in_the_way.push(item_entity),
+            EquipmentSlot::Shield if new_item.two_handed => in_the_way.push(item_entity),
+            EquipmentSlot::Melee if two_handed && new_item.slot == EquipmentSlot::Shield => {
+                in_the_way.push(item_entity)
             }
+            _ => {}
+        }
+    }
+    if rings.len() >= 2 {
+        let ring = rings
+            .iter()
+            .find(|ring| !cursed.contains(**ring))
+            .unwrap_or(&rings[0]);
+        in_the_way.push(*ring);
+    }
+    in_the_way
+}
 
-            // Healing
-            let item_heals = healing.get(use_item.item);
-            match item_heals {
-                None => {}
-                Some(healer) => {
-                    for target in targets.iter() {
-                        let stats = combat_stats.get_mut(*target);
-                        match stats {
-                            None => {}
-                            Some(stats) => {
-                                stats.hp = i32::min(stats.max_hp, stats.hp + healer.heal_amount);
-                                if entity == *player_entity {
-                                    gamelog.entries.push(format!(
-                                        "You use the {}, healing {} hp.",
-                                        names.get(use_item.item).unwrap().name,
-                                        healer.heal_amount
-                                    ));
-                                }
-                                let pos = positions.get(*player_entity);
-                                if let Some(pos) = pos {
-                                    particle_builder.request(
-                                        pos.x,
-                                        pos.y,
-                                        green(),
-                                        black(),
-                                        rltk::to_cp437('♥'),
-                                        200.0,
-                                    );
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            // Damaging
-            let item_damages = inflicts_damage.get(use_item.item);
-            match item_damages {
-                None => {}
-                Some(damage) => {
-                    used_item = false;
-                    for target in targets.iter() {
-                        match destructable.get(*target) {
-                            None => {}
-                            Some(_destructable) => {
-                                destructable
-                                    .insert(*target, Destructable { broken: true })
-                                    .expect("Unable to insert");
-                            }
-                        }
+    fn test_world() -> World {
+        let mut ecs = World::new();
+        ecs.register::<CursedItem>();
+        ecs.register::<Equippable>();
+        ecs.register::<Equipped>();
+        ecs
+    }
 
-                        SufferDamage::new_damage(&mut suffer_damage, *target, damage.damage);
+    fn wear(ecs: &mut World, owner: Entity, slot: EquipmentSlot, two_handed: bool) -> Entity {
+        ecs.create_entity()
+            .with(Equippable { slot, two_handed })
+            .with(Equipped { owner, slot })
+            .build()
+    }
 
-                        if entity == *player_entity {
-                            let target_name = names.get(*target).unwrap();
-                            let item_name = names.get(use_item.item).unwrap();
-                            gamelog.entries.push(format!(
-                                "You use {} on {}, inflicting {} damage.",
-                                item_name.name, target_name.name, damage.damage
-                            ));
-                        }
-                        used_item = true;
-                        let pos = positions.get(*target);
-                        if let Some(pos) = pos {
-                            particle_builder.request(
-                                pos.x,
-                                pos.y,
-                                red(),
-                                black(),
-                                rltk::to_cp437('‼'),
-                                200.0,
-                            );
-                        }
-                    }
-                }
-            }
+    fn in_the_way(ecs: &World, owner: Entity, new_item: Equippable) -> Vec<Entity> {
+        equipment_in_the_way(
+            &ecs.entities(),
+            &ecs.read_storage::<Equipped>(),
+            &ecs.read_storage::<Equippable>(),
+            &ecs.read_storage::<CursedItem>(),
+            owner,
+            &new_item,
+        )
+    }
 
-            // "Can it pass along confusion? Note the use of scopes to escape from the borrow checker!"
-            let mut add_confusion = Vec::new();
-            {
-                let causes_confusion = confused.get(use_item.item);
-                match causes_confusion {
-                    None => {}
-                    Some(confusion) => {
-                        used_item = false;
-                        for mob in targets.iter() {
-                            add_confusion.push((*mob, confusion.turns));
-                            if entity == *player_entity {
-                                let mob_name = names.get(*mob).unwrap();
-                                let item_name = names.get(use_item.item).unwrap();
-                                gamelog.entries.push(format!(
-                                    "You use {} on {}, confusing them.",
-                                    item_name.name, mob_name.name
-                                ));
-                            }
-                            let pos = positions.get(*mob);
-                            if let Some(pos) = pos {
-                                particle_builder.request(
-                                    pos.x,
-                                    pos.y,
-                                    magenta(),
-                                    black(),
-                                    rltk::to_cp437('?'),
-                                    1000.0,
-                                );
-                            }
-                        }
-                    }
-                }
-            }
-            for mob in add_confusion.iter() {
-                confused
-                    .insert(mob.0, Confusion { turns: mob.1 })
-                    .expect("Unable to insert status");
-                used_item = true;
-            }
+    #[test]
+    fn two_handed_weapon_and_shield_get_in_each_others_way() {
+        let mut ecs = test_world();
+        let owner = ecs.create_entity().build();
+        let sword = wear(&mut ecs, owner, EquipmentSlot::Melee, false);
+        let shield = wear(&mut ecs, owner, EquipmentSlot::Shield, false);
+        let greatsword = Equippable {
+            slot: EquipmentSlot::Melee,
+            two_handed: true,
+        };
+        let mut blocking = in_the_way(&ecs, owner, greatsword);
+        blocking.sort();
+        assert_eq!(blocking, vec![sword, shield]);
+
+        let mut ecs = test_world();
+        let owner = ecs.create_entity().build();
+        let greatsword = wear(&mut ecs, owner, EquipmentSlot::Melee, true);
+        let shield = Equippable {
+            slot: EquipmentSlot::Shield,
+            two_handed: false,
+        };
+        assert_eq!(in_the_way(&ecs, owner, shield), vec![greatsword]);
+    }
 
-            // delete consumed items
-            let consumable = consumables.get(use_item.item);
-            if used_item {
-                match consumable {
-                    None => {}
-                    Some(_) => {
-                        entities.delete(use_item.item).expect("Delete failed");
-                    }
-                }
-            }
-        } // big for-loop ends here: (&entities, &wants_use).join
+    #[test]
+    fn a_second_ring_fits_but_a_third_replaces_one() {
+        let ring = || Equippable {
+            slot: EquipmentSlot::Ring,
+            two_handed: false,
+        };
+        let mut ecs = test_world();
+        let owner = ecs.create_entity().build();
+        let first = wear(&mut ecs, owner, EquipmentSlot::Ring, false);
+        assert!(in_the_way(&ecs, owner, ring()).is_empty());
+
+        let second = wear(&mut ecs, owner, EquipmentSlot::Ring, false);
+        assert_eq!(in_the_way(&ecs, owner, ring()), vec![first]);
+
+        ecs.write_storage::<CursedItem>()
+            .insert(first, CursedItem {})
+            .expect("Unable to insert");
+        assert_eq!(in_the_way(&ecs, owner, ring()), vec![second]);
+
+        ecs.write_storage::<CursedItem>()
+            .insert(second, CursedItem {})
+            .expect("Unable to insert");
+        assert_eq!(in_the_way(&ecs, owner, ring()), vec![first]);
+    }
 
-        wants_use.clear();
+    #[test]
+    fn someone_elses_gear_is_never_in_the_way() {
+        let mut ecs = test_world();
+        let owner = ecs.create_entity().build();
+        let someone_else = ecs.create_entity().build();
+        wear(&mut ecs, someone_else, EquipmentSlot::Melee, true);
+        let sword = Equippable {
+            slot: EquipmentSlot::Melee,
+            two_handed: false,
+        };
+        assert!(in_the_way(&ecs, owner, sword).is_empty());
     }
 }
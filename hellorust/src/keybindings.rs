@@ -0,0 +1,394 @@
+// What the keys do. `player_input` and the map cursors only ever deal in `Command`s;
+// which key means which command comes from a preset, adjusted by keybindings.json.
+use rltk::VirtualKeyCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const KEYBINDINGS_FILE: &str = "./keybindings.json";
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub enum Command {
+    MoveNorth,
+    MoveSouth,
+    MoveWest,
+    MoveEast,
+    MoveNorthWest,
+    MoveNorthEast,
+    MoveSouthWest,
+    MoveSouthEast,
+    Wait,
+    Descend,
+    Ascend,
+    PickUp,
+    Inventory,
+    Drop,
+    Equipment,
+    CharacterSheet,
+    Look,
+    MessageLog,
+    Options,
+    Help,
+    /// Slot 0 to 8 of the spell hotbar.
+    CastSpell(usize),
+    SaveAndQuit,
+}
+
+impl Command {
+    /// Every command, in the order the help screen lists them.
+    pub fn all() -> Vec<Command> {
+        let mut commands = vec![
+            Command::MoveNorth,
+            Command::MoveSouth,
+            Command::MoveWest,
+            Command::MoveEast,
+            Command::MoveNorthWest,
+            Command::MoveNorthEast,
+            Command::MoveSouthWest,
+            Command::MoveSouthEast,
+            Command::Wait,
+            Command::Descend,
+            Command::Ascend,
+            Command::PickUp,
+            Command::Inventory,
+            Command::Drop,
+            Command::Equipment,
+            Command::CharacterSheet,
+            Command::Look,
+            Command::MessageLog,
+            Command::Options,
+            Command::Help,
+        ];
+        commands.extend((0..9).map(Command::CastSpell));
+        commands.push(Command::SaveAndQuit);
+        commands
+    }
+
+    /// The name used for this command in keybindings.json.
+    pub fn name(self) -> String {
+        match self {
+            Command::CastSpell(slot) => format!("CastSpell{}", slot + 1),
+            _ => format!("{:?}", self),
+        }
+    }
+
+    pub fn description(self) -> String {
+        match self {
+            Command::MoveNorth => "Move north".to_string(),
+            Command::MoveSouth => "Move south".to_string(),
+            Command::MoveWest => "Move west".to_string(),
+            Command::MoveEast => "Move east".to_string(),
+            Command::MoveNorthWest => "Move north-west".to_string(),
+            Command::MoveNorthEast => "Move north-east".to_string(),
+            Command::MoveSouthWest => "Move south-west".to_string(),
+            Command::MoveSouthEast => "Move south-east".to_string(),
+            Command::Wait => "Wait a turn".to_string(),
+            Command::Descend => "Go down stairs".to_string(),
+            Command::Ascend => "Go up stairs".to_string(),
+            Command::PickUp => "Pick up".to_string(),
+            Command::Inventory => "Inventory".to_string(),
+            Command::Drop => "Drop an item".to_string(),
+            Command::Equipment => "Equipment".to_string(),
+            Command::CharacterSheet => "Character sheet".to_string(),
+            Command::Look => "Look around".to_string(),
+            Command::MessageLog => "Message log".to_string(),
+            Command::Options => "Options".to_string(),
+            Command::Help => "This help".to_string(),
+            Command::CastSpell(slot) => format!("Cast spell {}", slot + 1),
+            Command::SaveAndQuit => "Save and quit".to_string(),
+        }
+    }
+
+    /// Which way a movement command goes.
+    pub fn direction(self) -> Option<(i32, i32)> {
+        match self {
+            Command::MoveNorth => Some((0, -1)),
+            Command::MoveSouth => Some((0, 1)),
+            Command::MoveWest => Some((-1, 0)),
+            Command::MoveEast => Some((1, 0)),
+            Command::MoveNorthWest => Some((-1, -1)),
+            Command::MoveNorthEast => Some((1, -1)),
+            Command::MoveSouthWest => Some((-1, 1)),
+            Command::MoveSouthEast => Some((1, 1)),
+            _ => None,
+        }
+    }
+}
+
+/// Keys that can be bound, by the name keybindings.json uses for them.
+const BINDABLE_KEYS: [VirtualKeyCode; 76] = {
+    use VirtualKeyCode::*;
+    [
+        A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, Key0, Key1,
+        Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Numpad0, Numpad1, Numpad2, Numpad3,
+        Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9, Left, Right, Up, Down, Home, End,
+        PageUp, PageDown, Insert, Delete, Space, Tab, Escape, Period, Comma, Slash, Semicolon,
+        Apostrophe, Minus, Equals, LBracket, RBracket, Backslash, Grave, F1, F2, F3, F4, F5, F6,
+    ]
+};
+
+pub fn key_name(key: VirtualKeyCode) -> String {
+    format!("{:?}", key)
+}
+
+fn parse_key(name: &str) -> Option<VirtualKeyCode> {
+    BINDABLE_KEYS
+        .iter()
+        .copied()
+        .find(|key| key_name(*key).eq_ignore_ascii_case(name))
+}
+
+fn parse_command(name: &str) -> Option<Command> {
+    Command::all()
+        .into_iter()
+        .find(|command| command.name().eq_ignore_ascii_case(name))
+}
+
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize, Debug, Default)]
+pub enum Preset {
+    /// Arrows, numpad, and WASD with QEZC for the diagonals.
+    #[default]
+    Default,
+    /// hjkl, with yubn for the diagonals.
+    Vi,
+    /// Like the default, for French keyboards: ZQSD with AEWC for the diagonals.
+    Azerty,
+}
+
+fn preset_bindings(preset: Preset) -> Vec<(Command, Vec<VirtualKeyCode>)> {
+    use VirtualKeyCode::*;
+    let (north, south, west, east, north_west, north_east, south_west, south_east, look) =
+        match preset {
+            Preset::Default => (W, S, A, D, Q, E, Z, C, L),
+            Preset::Vi => (K, J, H, L, Y, U, B, N, X),
+            Preset::Azerty => (Z, S, Q, D, A, E, W, C, L),
+        };
+    let mut bindings = vec![
+        (Command::MoveNorth, vec![Up, Numpad8, north]),
+        (Command::MoveSouth, vec![Down, Numpad2, south]),
+        (Command::MoveWest, vec![Left, Numpad4, west]),
+        (Command::MoveEast, vec![Right, Numpad6, east]),
+        (Command::MoveNorthWest, vec![Numpad7, north_west]),
+        (Command::MoveNorthEast, vec![Numpad9, north_east]),
+        (Command::MoveSouthWest, vec![Numpad1, south_west]),
+        (Command::MoveSouthEast, vec![Numpad3, south_east]),
+        (Command::Wait, vec![Space, Numpad5]),
+        (Command::Descend, vec![Period]),
+        (Command::Ascend, vec![Comma]),
+        (Command::PickUp, vec![G]),
+        (Command::Inventory, vec![I]),
+        (Command::Drop, vec![O]),
+        (Command::Equipment, vec![R]),
+        (Command::CharacterSheet, vec![P]),
+        (Command::Look, vec![look]),
+        (Command::MessageLog, vec![M]),
+        (Command::Options, vec![F2]),
+        (Command::Help, vec![Slash, F1]),
+    ];
+    let number_keys = [Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9];
+    for (slot, key) in number_keys.iter().enumerate() {
+        bindings.push((Command::CastSpell(slot), vec![*key]));
+    }
+    bindings.push((Command::SaveAndQuit, vec![Escape]));
+    bindings
+}
+
+/// What keybindings.json looks like: a preset, and the commands you'd like on other keys.
+/// `{ "preset": "Vi", "bindings": { "Look": ["Semicolon"] } }`
+#[derive(Serialize, Deserialize, Default)]
+struct KeymapFile {
+    #[serde(default)]
+    preset: Preset,
+    #[serde(default)]
+    bindings: HashMap<String, Vec<String>>,
+}
+
+pub struct Keymap {
+    pub preset: Preset,
+    /// In `Command::all()` order, so the help screen doesn't have to sort.
+    bindings: Vec<(Command, Vec<VirtualKeyCode>)>,
+    /// Everything wrong with the bindings file: unknown names and keys bound twice.
+    pub problems: Vec<String>,
+}
+
+impl Keymap {
+    pub fn from_preset(preset: Preset) -> Keymap {
+        Keymap {
+            preset,
+            bindings: preset_bindings(preset),
+            problems: Vec::new(),
+        }
+    }
+
+    /// The command a key stands for. If a key ended up bound twice, the command listed
+    /// first wins.
+    pub fn command_for(&self, key: VirtualKeyCode) -> Option<Command> {
+        self.bindings
+            .iter()
+            .find(|(_command, keys)| keys.contains(&key))
+            .map(|(command, _keys)| *command)
+    }
+
+    pub fn keys_for(&self, command: Command) -> Vec<VirtualKeyCode> {
+        self.bindings
+            .iter()
+            .find(|(c, _keys)| *c == command)
+            .map_or(Vec::new(), |(_command, keys)| keys.clone())
+    }
+
+    /// Which way a key moves, if it's bound to a movement command.
+    pub fn direction(&self, key: VirtualKeyCode) -> Option<(i32, i32)> {
+        self.command_for(key)
+            .and_then(|command| command.direction())
+    }
+
+    fn rebind(&mut self, command: Command, keys: Vec<VirtualKeyCode>) {
+        if let Some(binding) = self.bindings.iter_mut().find(|(c, _keys)| *c == command) {
+            binding.1 = keys;
+        }
+    }
+
+    /// Describes every key that is bound to more than one command.
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        for (i, (command, keys)) in self.bindings.iter().enumerate() {
+            for key in keys.iter() {
+                for (other, other_keys) in self.bindings.iter().skip(i + 1) {
+                    if other_keys.contains(key) {
+                        conflicts.push(format!(
+                            "{} is bound to both {} and {}; {} wins.",
+                            key_name(*key),
+                            command.name(),
+                            other.name(),
+                            command.name()
+                        ));
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+/// Reads keybindings.json, falling back to the default preset when there isn't one.
+pub fn load_keymap() -> Keymap {
+    match std::fs::read_to_string(KEYBINDINGS_FILE) {
+        Ok(data) => parse_keymap(&data),
+        Err(_) => Keymap::from_preset(Preset::Default),
+    }
+}
+
+/// Builds the keymap described by the contents of a keybindings file. Anything wrong
+/// with it ends up in `problems` rather than stopping the game from starting.
+fn parse_keymap(data: &str) -> Keymap {
+    let file: KeymapFile = match serde_json::from_str(data) {
+        Ok(file) => file,
+        Err(e) => {
+            let mut keymap = Keymap::from_preset(Preset::Default);
+            keymap
+                .problems
+                .push(format!("Couldn't read {}: {}", KEYBINDINGS_FILE, e));
+            return keymap;
+        }
+    };
+
+    let mut keymap = Keymap::from_preset(file.preset);
+    let mut problems = Vec::new();
+    for (command_name, key_names) in file.bindings.iter() {
+        let command = match parse_command(command_name) {
+            Some(command) => command,
+            None => {
+                problems.push(format!("Unknown command {} in key bindings.", command_name));
+                continue;
+            }
+        };
+        let mut keys = Vec::new();
+        for key_name in key_names.iter() {
+            match parse_key(key_name) {
+                Some(key) => keys.push(key),
+                None => problems.push(format!("Unknown key {} for {}.", key_name, command_name)),
+            }
+        }
+        keymap.rebind(command, keys);
+    }
+    problems.extend(keymap.conflicts());
+    keymap.problems = problems;
+    keymap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_preset_binds_a_key_twice() {
+        for preset in [Preset::Default, Preset::Vi, Preset::Azerty].iter() {
+            let conflicts = Keymap::from_preset(*preset).conflicts();
+            assert!(conflicts.is_empty(), "{:?}: {:?}", preset, conflicts);
+        }
+    }
+
+    #[test]
+    fn vi_moves_east_with_l_and_looks_with_x() {
+        let keymap = Keymap::from_preset(Preset::Vi);
+        assert_eq!(
+            keymap.command_for(VirtualKeyCode::L),
+            Some(Command::MoveEast)
+        );
+        assert_eq!(keymap.command_for(VirtualKeyCode::X), Some(Command::Look));
+    }
+
+    #[test]
+    fn the_file_picks_the_preset_and_rebinds_commands() {
+        let keymap = parse_keymap(r#"{ "preset": "Vi", "bindings": { "Look": ["Semicolon"] } }"#);
+        assert_eq!(keymap.preset, Preset::Vi);
+        assert!(keymap.problems.is_empty(), "{:?}", keymap.problems);
+        assert_eq!(
+            keymap.keys_for(Command::Look),
+            vec![VirtualKeyCode::Semicolon]
+        );
+        assert_eq!(keymap.command_for(VirtualKeyCode::X), None);
+    }
+
+    #[test]
+    fn names_match_regardless_of_case() {
+        let keymap = parse_keymap(r#"{ "bindings": { "castspell1": ["f3", "NUMPAD0"] } }"#);
+        assert!(keymap.problems.is_empty(), "{:?}", keymap.problems);
+        assert_eq!(
+            keymap.keys_for(Command::CastSpell(0)),
+            vec![VirtualKeyCode::F3, VirtualKeyCode::Numpad0]
+        );
+    }
+
+    #[test]
+    fn unknown_commands_and_keys_are_reported() {
+        let keymap =
+            parse_keymap(r#"{ "bindings": { "Dance": ["F3"], "Look": ["Semicolon", "Banana"] } }"#);
+        assert_eq!(keymap.problems.len(), 2, "{:?}", keymap.problems);
+        assert!(keymap.problems.iter().any(|p| p.contains("Dance")));
+        assert!(keymap.problems.iter().any(|p| p.contains("Banana")));
+        // the keys that did make sense still count
+        assert_eq!(
+            keymap.keys_for(Command::Look),
+            vec![VirtualKeyCode::Semicolon]
+        );
+    }
+
+    #[test]
+    fn binding_a_key_that_is_already_taken_is_reported() {
+        let keymap = parse_keymap(r#"{ "bindings": { "Look": ["G"] } }"#);
+        assert_eq!(keymap.problems.len(), 1, "{:?}", keymap.problems);
+        assert!(keymap.problems[0].contains("PickUp"));
+        assert!(keymap.problems[0].contains("Look"));
+    }
+
+    #[test]
+    fn a_broken_file_falls_back_to_the_default_preset() {
+        let keymap = parse_keymap("{ not json");
+        assert_eq!(keymap.preset, Preset::Default);
+        assert_eq!(keymap.problems.len(), 1);
+        assert_eq!(
+            keymap.command_for(VirtualKeyCode::W),
+            Some(Command::MoveNorth)
+        );
+    }
+}
@@ -11,8 +11,11 @@ mod color;
 mod components;
 mod gamelog;
 mod gui;
+mod identification;
 mod inventory_system;
+mod keybindings;
 mod spawner;
+mod trade;
 pub use components::*;
 use inventory_system::{
     EquipmentRemoveSystem, ItemCollectionSystem, ItemDropSystem, ItemUseSystem,
@@ -25,49 +28,107 @@ mod visibility_system;
 use visibility_system::VisibilitySystem;
 mod monster_ai_system;
 use monster_ai_system::MonsterAI;
+mod monster_item_ai_system;
+use monster_item_ai_system::MonsterItemAI;
+mod ranged_ai_system;
+use ranged_ai_system::RangedMonsterAI;
+mod companion_ai_system;
+use companion_ai_system::CompanionAI;
+mod bystander_ai_system;
+use bystander_ai_system::BystanderAI;
 mod map_indexing_system;
 use map_indexing_system::MapIndexingSystem;
 mod melee_combat_system;
 use melee_combat_system::MeleeCombatSystem;
+mod boss_system;
+mod character_creation;
 mod damage_system;
-use damage_system::DamageSystem;
+mod effects;
+mod encumbrance;
+mod examine;
+mod experience;
+mod morgue;
 mod random_table;
 mod saveload_system;
+mod settings;
+mod statistics;
 use random_table::RandomTable;
 mod particle_system;
 use particle_system::{ParticleBuilder, ParticleSpawnSystem};
 mod hunger_system;
 use hunger_system::HungerSystem;
+mod corpse_system;
+use corpse_system::CorpseSystem;
+mod mana_system;
+use mana_system::ManaSystem;
+mod confusion_system;
+use confusion_system::ConfusionSystem;
 mod trigger_system;
 use trigger_system::TriggerSystem;
 pub mod map_builders;
 mod rex_assets;
 
-const SHOW_MAPGEN_VISUALIZER: bool = true;
-
 #[derive(PartialEq, Copy, Clone)]
 pub enum RunState {
     AwaitingInput,
     PreRun,
     PlayerTurn,
     MonsterTurn,
-    ShowInventory,
-    ShowDropItem,
+    ShowInventory {
+        page: usize,
+    },
+    ShowDropItem {
+        page: usize,
+    },
     ShowTargeting {
         range: i32,
         item: Entity,
+        /// Where the keyboard cursor is aiming, once it has been placed.
+        cursor: Option<Point>,
     },
     MainMenu {
         menu_selection: gui::MainMenuSelection,
     },
     SaveGame,
     NextLevel,
+    PreviousLevel,
     MagicMapReveal {
         row: i32,
     },
     MapGeneration,
-    ShowRemoveItem,
+    ShowEquipment,
+    ShowIdentify {
+        scroll: Entity,
+        page: usize,
+    },
+    ShowRemoveCurse {
+        scroll: Entity,
+        page: usize,
+    },
+    ShowTrade {
+        vendor: Entity,
+        mode: gui::TradeMode,
+        page: usize,
+    },
     GameOver,
+    Victory,
+    ShowHighScores,
+    ShowLog,
+    CharacterCreation {
+        step: gui::CreationStep,
+        selected: usize,
+    },
+    ShowCharacterSheet,
+    ShowHelp,
+    ShowOptions {
+        selected: usize,
+        /// Where Escape goes back to: the main menu, or the game.
+        from_main_menu: bool,
+    },
+    LookMode {
+        x: i32,
+        y: i32,
+    },
 }
 
 pub struct State {
@@ -89,8 +150,13 @@ impl GameState for State {
         particle_system::cull_dead_particles(&mut self.ecs, ctx);
 
         match new_runstate {
-            RunState::MainMenu { .. } => {}
-            RunState::GameOver { .. } => {}
+            RunState::MainMenu { .. } | RunState::ShowHighScores | RunState::ShowLog => {}
+            RunState::ShowOptions {
+                from_main_menu: true,
+                ..
+            } => {}
+            RunState::CharacterCreation { .. } => {}
+            RunState::GameOver | RunState::Victory => {}
             _ => {
                 draw_map(&self.ecs.fetch::<Map>(), ctx);
                 {
@@ -105,7 +171,7 @@ impl GameState for State {
                     let mut data = (&positions, &renderables, !&hidden)
                         .join()
                         .collect::<Vec<_>>();
-                    data.sort_by(|&a, &b| b.1.render_order.cmp(&a.1.render_order));
+                    data.sort_by_key(|&a| std::cmp::Reverse(a.1.render_order));
                     for (pos, render, _hidden) in data.iter() {
                         let idx = map.xy_idx(pos.x, pos.y);
                         if map.visible_tiles[idx] {
@@ -120,21 +186,26 @@ impl GameState for State {
 
         match new_runstate {
             RunState::MapGeneration => {
-                if !SHOW_MAPGEN_VISUALIZER {
+                let (show_visualizer, step_ms) = {
+                    let settings = self.ecs.fetch::<settings::Settings>();
+                    (settings.show_mapgen_visualizer, settings.mapgen_step_ms)
+                };
+                if !show_visualizer || self.mapgen_history.is_empty() {
                     new_runstate = self.mapgen_next_state.unwrap();
-                }
-                ctx.cls();
-                draw_map(&self.mapgen_history[self.mapgen_index], ctx);
-
-                // "Add the frame duration to the mapgen_timer..."
-                self.mapgen_timer += ctx.frame_time_ms;
-                if self.mapgen_timer > 300.0 {
-                    self.mapgen_timer = 0.0;
-                    self.mapgen_index += 1;
-                    // if the frame counter has reached the end of our history...
-                    if self.mapgen_index >= self.mapgen_history.len() {
-                        // ...transition to the next game state.
-                        new_runstate = self.mapgen_next_state.unwrap();
+                } else {
+                    ctx.cls();
+                    draw_map(&self.mapgen_history[self.mapgen_index], ctx);
+
+                    // "Add the frame duration to the mapgen_timer..."
+                    self.mapgen_timer += ctx.frame_time_ms;
+                    if self.mapgen_timer > step_ms {
+                        self.mapgen_timer = 0.0;
+                        self.mapgen_index += 1;
+                        // if the frame counter has reached the end of our history...
+                        if self.mapgen_index >= self.mapgen_history.len() {
+                            // ...transition to the next game state.
+                            new_runstate = self.mapgen_next_state.unwrap();
+                        }
                     }
                 }
             }
@@ -147,6 +218,12 @@ impl GameState for State {
                 new_runstate = player_input(self, ctx);
             }
             RunState::PlayerTurn => {
+                let turn = {
+                    let mut stats = self.ecs.write_resource::<statistics::RunStats>();
+                    stats.turns += 1;
+                    stats.turns
+                };
+                self.ecs.write_resource::<gamelog::GameLog>().turn = turn;
                 self.run_systems();
                 self.ecs.maintain();
                 match *self.ecs.fetch::<RunState>() {
@@ -160,23 +237,57 @@ impl GameState for State {
                 self.run_systems();
                 self.ecs.maintain();
                 new_runstate = RunState::AwaitingInput;
+
+                // Carrying too much slows you down: sometimes the monsters get another go.
+                let player_entity = *self.ecs.fetch::<Entity>();
+                let chance = encumbrance::extra_turn_chance(encumbrance::encumbrance(
+                    &self.ecs,
+                    player_entity,
+                ));
+                if chance > 0
+                    && self
+                        .ecs
+                        .fetch_mut::<RandomNumberGenerator>()
+                        .roll_dice(1, 100)
+                        <= chance
+                {
+                    new_runstate = RunState::MonsterTurn;
+                }
             }
-            RunState::ShowInventory => {
-                let result = gui::show_inventory(self, ctx);
+            RunState::ShowInventory { page } => {
+                let result = gui::show_inventory(self, ctx, page);
                 match result.0 {
                     gui::ItemMenuResult::Cancel => new_runstate = RunState::AwaitingInput,
                     gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::ChangePage { page } => {
+                        new_runstate = RunState::ShowInventory { page }
+                    }
                     gui::ItemMenuResult::Selected => {
                         let item_entity = result.1.unwrap();
                         let is_ranged = self.ecs.read_storage::<Ranged>();
                         let is_item_ranged = is_ranged.get(item_entity);
+                        let identifies = self.ecs.read_storage::<IdentifiesItem>();
+                        let removes_curse = self.ecs.read_storage::<ProvidesRemoveCurse>();
 
                         // "handle items that are ranged and induce a mode switch"
                         match is_item_ranged {
+                            None if identifies.get(item_entity).is_some() => {
+                                new_runstate = RunState::ShowIdentify {
+                                    scroll: item_entity,
+                                    page: 0,
+                                }
+                            }
+                            None if removes_curse.get(item_entity).is_some() => {
+                                new_runstate = RunState::ShowRemoveCurse {
+                                    scroll: item_entity,
+                                    page: 0,
+                                }
+                            }
                             Some(ranged_item) => {
                                 new_runstate = RunState::ShowTargeting {
                                     range: ranged_item.range,
                                     item: item_entity,
+                                    cursor: None,
                                 }
                             }
                             None => {
@@ -196,11 +307,14 @@ impl GameState for State {
                     }
                 }
             }
-            RunState::ShowDropItem => {
-                let result = gui::show_drop_item_menu(self, ctx);
+            RunState::ShowDropItem { page } => {
+                let result = gui::show_drop_item_menu(self, ctx, page);
                 match result.0 {
                     gui::ItemMenuResult::Cancel => new_runstate = RunState::AwaitingInput,
                     gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::ChangePage { page } => {
+                        new_runstate = RunState::ShowDropItem { page }
+                    }
                     gui::ItemMenuResult::Selected => {
                         let item_entity = result.1.unwrap();
                         let mut intent = self.ecs.write_storage::<WantsToDropItem>();
@@ -214,19 +328,29 @@ impl GameState for State {
                     }
                 }
             }
-            RunState::ShowTargeting { range, item } => {
-                let result = gui::ranged_target(self, ctx, range);
-                match result.0 {
-                    gui::ItemMenuResult::Cancel => new_runstate = RunState::AwaitingInput,
-                    gui::ItemMenuResult::NoResponse => {}
-                    gui::ItemMenuResult::Selected => {
+            RunState::ShowTargeting {
+                range,
+                item,
+                cursor,
+            } => {
+                let result = gui::ranged_target(self, ctx, range, item, cursor);
+                match result {
+                    gui::TargetResult::Cancel => new_runstate = RunState::AwaitingInput,
+                    gui::TargetResult::NoResponse { cursor } => {
+                        new_runstate = RunState::ShowTargeting {
+                            range,
+                            item,
+                            cursor,
+                        }
+                    }
+                    gui::TargetResult::Selected { target } => {
                         let mut intent = self.ecs.write_storage::<WantsToUseItem>();
                         intent
                             .insert(
                                 *self.ecs.fetch::<Entity>(),
                                 WantsToUseItem {
-                                    item: item,
-                                    target: result.1,
+                                    item,
+                                    target: Some(target),
                                 },
                             )
                             .expect("Unable to insert intent");
@@ -245,13 +369,31 @@ impl GameState for State {
                     gui::MainMenuResult::Selected { selected } => match selected {
                         gui::MainMenuSelection::NewGame => {
                             saveload_system::delete_save();
-                            new_runstate = RunState::PreRun
+                            // Quitting to the menu leaves the old character around
+                            let player_entity = *self.ecs.fetch::<Entity>();
+                            if self.ecs.read_storage::<Character>().contains(player_entity) {
+                                self.game_over_cleanup();
+                            }
+                            self.ecs.insert(character_creation::CharacterDraft::new());
+                            new_runstate = RunState::CharacterCreation {
+                                step: gui::CreationStep::Race,
+                                selected: 0,
+                            }
                         }
                         gui::MainMenuSelection::LoadGame => {
                             saveload_system::load_game(&mut self.ecs);
                             new_runstate = RunState::PreRun;
                             saveload_system::delete_save();
                         }
+                        gui::MainMenuSelection::HighScores => {
+                            new_runstate = RunState::ShowHighScores;
+                        }
+                        gui::MainMenuSelection::Options => {
+                            new_runstate = RunState::ShowOptions {
+                                selected: 0,
+                                from_main_menu: true,
+                            };
+                        }
                         gui::MainMenuSelection::Quit => {
                             std::process::exit(0);
                         }
@@ -268,11 +410,21 @@ impl GameState for State {
                 self.goto_next_level();
                 new_runstate = RunState::PreRun;
             }
-            RunState::ShowRemoveItem => {
-                let result = gui::remove_equipment_menu(self, ctx);
+            RunState::PreviousLevel => {
+                self.goto_previous_level();
+                // Made it out of the dungeon with the artifact
+                if self.ecs.fetch::<Map>().depth == 0 && artifact_recovered(&self.ecs) {
+                    statistics::record_run(&self.ecs, true);
+                    new_runstate = RunState::Victory;
+                } else {
+                    new_runstate = RunState::PreRun;
+                }
+            }
+            RunState::ShowEquipment => {
+                let result = gui::equipment_menu(self, ctx);
                 match result.0 {
                     gui::ItemMenuResult::Cancel => new_runstate = RunState::AwaitingInput,
-                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::NoResponse | gui::ItemMenuResult::ChangePage { .. } => {}
                     gui::ItemMenuResult::Selected => {
                         let item_entity = result.1.unwrap();
                         let mut intent = self.ecs.write_storage::<WantsToRemoveEquipment>();
@@ -286,6 +438,82 @@ impl GameState for State {
                     }
                 }
             }
+            RunState::ShowIdentify { scroll, page } => {
+                let result = gui::identify_menu(self, ctx, scroll, page);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => new_runstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::ChangePage { page } => {
+                        new_runstate = RunState::ShowIdentify { scroll, page }
+                    }
+                    gui::ItemMenuResult::Selected => {
+                        let item_entity = result.1.unwrap();
+                        identification::identify_item(&mut self.ecs, scroll);
+                        identification::identify_item(&mut self.ecs, item_entity);
+                        self.ecs
+                            .delete_entity(scroll)
+                            .expect("Unable to delete scroll");
+                        new_runstate = RunState::PlayerTurn;
+                    }
+                }
+            }
+            RunState::ShowRemoveCurse { scroll, page } => {
+                let result = gui::remove_curse_menu(self, ctx, page);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => new_runstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::ChangePage { page } => {
+                        new_runstate = RunState::ShowRemoveCurse { scroll, page }
+                    }
+                    gui::ItemMenuResult::Selected => {
+                        let item_entity = result.1.unwrap();
+                        identification::identify_item(&mut self.ecs, scroll);
+                        self.ecs.write_storage::<CursedItem>().remove(item_entity);
+                        let item_name = identification::item_display_name(&self.ecs, item_entity);
+                        self.ecs.fetch_mut::<gamelog::GameLog>().add(format!(
+                            "You feel a malevolent presence leave the {}.",
+                            item_name
+                        ));
+                        self.ecs
+                            .delete_entity(scroll)
+                            .expect("Unable to delete scroll");
+                        new_runstate = RunState::PlayerTurn;
+                    }
+                }
+            }
+            RunState::ShowTrade { vendor, mode, page } => {
+                let result = gui::trade_menu(self, ctx, vendor, mode, page);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => new_runstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {
+                        if ctx.key == Some(rltk::VirtualKeyCode::Tab) {
+                            let mode = match mode {
+                                gui::TradeMode::Buy => gui::TradeMode::Sell,
+                                gui::TradeMode::Sell => gui::TradeMode::Buy,
+                            };
+                            new_runstate = RunState::ShowTrade {
+                                vendor,
+                                mode,
+                                page: 0,
+                            };
+                        }
+                    }
+                    gui::ItemMenuResult::ChangePage { page } => {
+                        new_runstate = RunState::ShowTrade { vendor, mode, page }
+                    }
+                    gui::ItemMenuResult::Selected => {
+                        let item_entity = result.1.unwrap();
+                        match mode {
+                            gui::TradeMode::Buy => {
+                                trade::buy_item(&mut self.ecs, vendor, item_entity)
+                            }
+                            gui::TradeMode::Sell => {
+                                trade::sell_item(&mut self.ecs, vendor, item_entity)
+                            }
+                        }
+                    }
+                }
+            }
             RunState::MagicMapReveal { row } => {
                 let mut map = self.ecs.fetch_mut::<Map>();
                 for x in 0..MAP_WIDTH {
@@ -298,8 +526,115 @@ impl GameState for State {
                     new_runstate = RunState::MagicMapReveal { row: row + 1 };
                 }
             }
+            RunState::ShowHighScores => {
+                if gui::high_score_table(ctx) == gui::ItemMenuResult::Cancel {
+                    new_runstate = RunState::MainMenu {
+                        menu_selection: gui::MainMenuSelection::HighScores,
+                    };
+                }
+            }
+            RunState::CharacterCreation { step, selected } => {
+                let result = gui::character_creation(&self.ecs, ctx, step, selected);
+                match result {
+                    gui::CreationResult::NoSelection { selected } => {
+                        new_runstate = RunState::CharacterCreation { step, selected }
+                    }
+                    gui::CreationResult::Back => {
+                        new_runstate = match step {
+                            gui::CreationStep::Race => RunState::MainMenu {
+                                menu_selection: gui::MainMenuSelection::NewGame,
+                            },
+                            gui::CreationStep::Class => RunState::CharacterCreation {
+                                step: gui::CreationStep::Race,
+                                selected: 0,
+                            },
+                            gui::CreationStep::Name => RunState::CharacterCreation {
+                                step: gui::CreationStep::Class,
+                                selected: 0,
+                            },
+                        }
+                    }
+                    gui::CreationResult::Selected { selected } => match step {
+                        gui::CreationStep::Race => {
+                            self.ecs
+                                .fetch_mut::<character_creation::CharacterDraft>()
+                                .race = character_creation::RACES[selected];
+                            new_runstate = RunState::CharacterCreation {
+                                step: gui::CreationStep::Class,
+                                selected: 0,
+                            }
+                        }
+                        gui::CreationStep::Class => {
+                            self.ecs
+                                .fetch_mut::<character_creation::CharacterDraft>()
+                                .class = character_creation::CLASSES[selected];
+                            new_runstate = RunState::CharacterCreation {
+                                step: gui::CreationStep::Name,
+                                selected: 0,
+                            }
+                        }
+                        gui::CreationStep::Name => {
+                            character_creation::create_character(&mut self.ecs);
+                            new_runstate = RunState::PreRun;
+                        }
+                    },
+                }
+            }
+            RunState::LookMode { x, y } => match gui::look_mode(&self.ecs, ctx, x, y) {
+                gui::LookResult::NoResponse => {}
+                gui::LookResult::Moved { x, y } => new_runstate = RunState::LookMode { x, y },
+                gui::LookResult::Cancel => new_runstate = RunState::AwaitingInput,
+            },
+            RunState::ShowOptions {
+                selected,
+                from_main_menu,
+            } => match gui::options_menu(&self.ecs, ctx, selected) {
+                gui::OptionsResult::NoSelection { selected } => {
+                    new_runstate = RunState::ShowOptions {
+                        selected,
+                        from_main_menu,
+                    };
+                }
+                gui::OptionsResult::Close => {
+                    settings::save_settings(&self.ecs.fetch::<settings::Settings>());
+                    new_runstate = if from_main_menu {
+                        RunState::MainMenu {
+                            menu_selection: gui::MainMenuSelection::Options,
+                        }
+                    } else {
+                        RunState::AwaitingInput
+                    };
+                }
+            },
+            RunState::ShowHelp => {
+                if gui::help_screen(&self.ecs, ctx) == gui::ItemMenuResult::Cancel {
+                    new_runstate = RunState::AwaitingInput;
+                }
+            }
+            RunState::ShowCharacterSheet => {
+                if gui::character_sheet(&self.ecs, ctx) == gui::ItemMenuResult::Cancel {
+                    new_runstate = RunState::AwaitingInput;
+                }
+            }
+            RunState::ShowLog => {
+                if gui::log_viewer(&self.ecs, ctx) == gui::ItemMenuResult::Cancel {
+                    new_runstate = RunState::AwaitingInput;
+                }
+            }
             RunState::GameOver => {
-                let result = gui::game_over(ctx);
+                let result = gui::game_over(&self.ecs, ctx);
+                match result {
+                    gui::GameOverResult::QuitToMenu => {
+                        self.game_over_cleanup();
+                        new_runstate = RunState::MainMenu {
+                            menu_selection: gui::MainMenuSelection::NewGame,
+                        };
+                    }
+                    gui::GameOverResult::NoSelection => {}
+                }
+            }
+            RunState::Victory => {
+                let result = gui::victory(&self.ecs, ctx);
                 match result {
                     gui::GameOverResult::QuitToMenu => {
                         self.game_over_cleanup();
@@ -326,16 +661,22 @@ impl State {
     fn run_systems(&mut self) {
         let mut vis = VisibilitySystem {};
         vis.run_now(&self.ecs);
+        let mut mob_items = MonsterItemAI {};
+        mob_items.run_now(&self.ecs);
+        let mut ranged_mobs = RangedMonsterAI {};
+        ranged_mobs.run_now(&self.ecs);
         let mut mob = MonsterAI {};
         mob.run_now(&self.ecs);
+        let mut companions = CompanionAI {};
+        companions.run_now(&self.ecs);
+        let mut bystanders = BystanderAI {};
+        bystanders.run_now(&self.ecs);
         let mut trigger_system = TriggerSystem {};
         trigger_system.run_now(&self.ecs);
         let mut mapindex = MapIndexingSystem {};
         mapindex.run_now(&self.ecs);
         let mut melee = MeleeCombatSystem {};
         melee.run_now(&self.ecs);
-        let mut damage = DamageSystem {};
-        damage.run_now(&self.ecs);
         let mut pickup = ItemCollectionSystem {};
         pickup.run_now(&self.ecs);
         let mut drop_items = ItemDropSystem {};
@@ -344,10 +685,18 @@ impl State {
         use_items.run_now(&self.ecs);
         let mut remove_equipment = EquipmentRemoveSystem {};
         remove_equipment.run_now(&self.ecs);
-        let mut particle_system = ParticleSpawnSystem {};
-        particle_system.run_now(&self.ecs);
         let mut hunger_system = HungerSystem {};
         hunger_system.run_now(&self.ecs);
+        let mut mana_system = ManaSystem {};
+        mana_system.run_now(&self.ecs);
+        let mut confusion_system = ConfusionSystem {};
+        confusion_system.run_now(&self.ecs);
+        let mut corpse_system = CorpseSystem {};
+        corpse_system.run_now(&self.ecs);
+        effects::run_effects_queue(&mut self.ecs);
+        boss_system::boss_phases(&mut self.ecs);
+        let mut particle_system = ParticleSpawnSystem {};
+        particle_system.run_now(&self.ecs);
         // "We've made the particle system depend upon likely particle
         // spawners. We'll have to be a little careful to avoid
         // accidentally making it concurrent with anything that might
@@ -360,7 +709,16 @@ impl State {
         self.mapgen_timer = 0.0;
         self.mapgen_history.clear();
 
-        let mut builder = map_builders::random_builder(new_depth);
+        {
+            let mut run_stats = self.ecs.write_resource::<statistics::RunStats>();
+            run_stats.deepest_depth = i32::max(run_stats.deepest_depth, new_depth);
+        }
+
+        let show_visualizer = self
+            .ecs
+            .fetch::<settings::Settings>()
+            .show_mapgen_visualizer;
+        let mut builder = map_builders::random_builder(new_depth, show_visualizer);
         builder.build_map();
         self.mapgen_history = builder.get_snapshot_history();
 
@@ -372,27 +730,39 @@ impl State {
             player_start = builder.get_starting_position();
         }
 
+        // With the artifact in hand, every level has a way back up where you come in
+        if new_depth > 0 && artifact_recovered(&self.ecs) {
+            let mut map = self.ecs.write_resource::<Map>();
+            let start_idx = map.xy_idx(player_start.x, player_start.y);
+            map.tiles[start_idx] = TileType::UpStairs;
+        }
+
         // spawn baddies
         builder.spawn_entities(&mut self.ecs);
 
-        // position our 'guy' and update resources
-        let (player_x, player_y) = (player_start.x, player_start.y);
-        let mut player_position = self.ecs.write_resource::<Point>();
-        *player_position = Point::new(player_x, player_y);
-        let mut position_components = self.ecs.write_storage::<Position>();
-        let player_entity = self.ecs.fetch::<Entity>();
-        let player_pos_comp = position_components.get_mut(*player_entity);
-        if let Some(player_pos_comp) = player_pos_comp {
-            player_pos_comp.x = player_x;
-            player_pos_comp.y = player_y;
-        }
+        {
+            // position our 'guy' and update resources
+            let (player_x, player_y) = (player_start.x, player_start.y);
+            let mut player_position = self.ecs.write_resource::<Point>();
+            *player_position = Point::new(player_x, player_y);
+            let mut position_components = self.ecs.write_storage::<Position>();
+            let player_entity = self.ecs.fetch::<Entity>();
+            let player_pos_comp = position_components.get_mut(*player_entity);
+            if let Some(player_pos_comp) = player_pos_comp {
+                player_pos_comp.x = player_x;
+                player_pos_comp.y = player_y;
+            }
 
-        // Mark the player's visibility as dirty, systems will take care of it
-        let mut viewshed_components = self.ecs.write_storage::<Viewshed>();
-        let vs = viewshed_components.get_mut(*player_entity);
-        if let Some(vs) = vs {
-            vs.dirty = true;
+            // Mark the player's visibility as dirty, systems will take care of it
+            let mut viewshed_components = self.ecs.write_storage::<Viewshed>();
+            let vs = viewshed_components.get_mut(*player_entity);
+            if let Some(vs) = vs {
+                vs.dirty = true;
+            }
         }
+
+        // The player's companions follow them to the new map
+        companion_ai_system::place_companions_near_player(&mut self.ecs);
     }
 
     fn entities_to_remove_on_level_change(&mut self) -> Vec<Entity> {
@@ -400,6 +770,8 @@ impl State {
         let player = self.ecs.read_storage::<Player>();
         let backpack = self.ecs.read_storage::<InBackpack>();
         let equipped_items = self.ecs.read_storage::<Equipped>();
+        let known_spells = self.ecs.read_storage::<KnownSpell>();
+        let companions = self.ecs.read_storage::<Companion>();
         let player_entity = self.ecs.fetch::<Entity>();
 
         let mut to_delete: Vec<Entity> = Vec::new();
@@ -420,6 +792,16 @@ impl State {
                     should_delete = false;
                 }
             }
+            // Companions come along
+            if companions.get(entity).is_some() {
+                should_delete = false;
+            }
+            // Don't forget the player's spells
+            if let Some(spell) = known_spells.get(entity) {
+                if spell.owner == *player_entity {
+                    should_delete = false;
+                }
+            }
             // Don't delete the player's equiped items
             let eq_item = equipped_items.get(entity);
             if let Some(item) = eq_item {
@@ -455,9 +837,10 @@ impl State {
 
         // Notify the player and give them some health
         let mut gamelog = self.ecs.fetch_mut::<gamelog::GameLog>();
-        gamelog
-            .entries
-            .push("You descend to the next level, and take a moment to heal.".to_string());
+        gamelog.add("You descend to the next level, and take a moment to heal.".to_string());
+        if map_builders::is_boss_depth(current_depth + 1) {
+            gamelog.add("A heavy gate bars the way down. Something big is waiting.".to_string());
+        }
         let mut player_health_store = self.ecs.write_storage::<CombatStats>();
         let player_health = player_health_store.get_mut(*player_entity);
         if let Some(player_health) = player_health {
@@ -466,6 +849,61 @@ impl State {
         }
     }
 
+    /// Going up lands you on the down stairs of a freshly generated level. Only the first level
+    /// has a way up, until the artifact has been found. The town is built by hand, so its layout
+    /// is the same as when you left and you come out of the dungeon on its entrance, but the
+    /// townsfolk and the vendors' stock are new: whatever you sold them is gone.
+    fn goto_previous_level(&mut self) {
+        let to_delete = self.entities_to_remove_on_level_change();
+        for target in to_delete {
+            self.ecs
+                .delete_entity(target)
+                .expect("Unable to delete entity");
+        }
+
+        let current_depth = self.ecs.fetch::<Map>().depth;
+        self.generate_world_map(current_depth - 1);
+
+        let stairs_idx = {
+            let map = self.ecs.fetch::<Map>();
+            map.tiles
+                .iter()
+                .position(|tile| *tile == TileType::DownStairs)
+        };
+        if let Some(stairs_idx) = stairs_idx {
+            let (x, y) = (
+                stairs_idx as i32 % MAP_WIDTH as i32,
+                stairs_idx as i32 / MAP_WIDTH as i32,
+            );
+            *self.ecs.write_resource::<Point>() = Point::new(x, y);
+            let player_entity = *self.ecs.fetch::<Entity>();
+            if let Some(pos) = self.ecs.write_storage::<Position>().get_mut(player_entity) {
+                pos.x = x;
+                pos.y = y;
+            }
+            companion_ai_system::place_companions_near_player(&mut self.ecs);
+        }
+
+        // Coming up from below, the gates of a boss arena have already been opened
+        {
+            let mut map = self.ecs.write_resource::<Map>();
+            for tile in map.tiles.iter_mut() {
+                if *tile == TileType::SealedGate {
+                    *tile = TileType::Floor;
+                }
+            }
+        }
+
+        let message = if current_depth - 1 == 0 {
+            "You climb back up into the daylight."
+        } else {
+            "You climb back up the stairs."
+        };
+        self.ecs
+            .fetch_mut::<gamelog::GameLog>()
+            .add(message.to_string());
+    }
+
     fn game_over_cleanup(&mut self) {
         // Mark and delete all entities
         let mut to_delete = Vec::new();
@@ -483,15 +921,24 @@ impl State {
         }
 
         // Flush the gamelog
-        self.ecs.insert(gamelog::GameLog { entries: vec![] });
-        self.generate_world_map(1);
+        self.ecs.insert(gamelog::GameLog::new());
+        settings::apply_settings(&self.ecs);
+        // A new run gets new names for its scrolls and potions
+        self.ecs.insert(identification::MagicItemNames::new());
+        self.ecs.insert(statistics::RunStats::new());
+        self.generate_world_map(0);
     }
 }
 
 fn main() -> rltk::BError {
-    let context = RltkBuilder::simple80x50()
+    let settings = settings::load_settings();
+    let builder = match settings.font {
+        settings::Font::Terminal8x8 => RltkBuilder::simple80x50(),
+        settings::Font::Vga8x16 => RltkBuilder::vga80x50(),
+    };
+    let context = builder
         .with_title("Hello Rust World")
-        .with_fullscreen(true)
+        .with_fullscreen(settings.fullscreen)
         .build()?;
 
     let main_menu_selection = if saveload_system::save_exists() {
@@ -518,7 +965,6 @@ fn main() -> rltk::BError {
     gs.ecs.register::<BlocksTile>();
     gs.ecs.register::<CombatStats>();
     gs.ecs.register::<WantsToMelee>();
-    gs.ecs.register::<SufferDamage>();
     gs.ecs.register::<Item>();
     gs.ecs.register::<InBackpack>();
     gs.ecs.register::<WantsToPickupItem>();
@@ -546,23 +992,57 @@ fn main() -> rltk::BError {
     gs.ecs.register::<EntryTrigger>();
     gs.ecs.register::<EntityMoved>();
     gs.ecs.register::<SingleActivation>();
+    gs.ecs.register::<RandomTeleport>();
+    gs.ecs.register::<ObfuscatedName>();
+    gs.ecs.register::<IdentifiesItem>();
+    gs.ecs.register::<CursedItem>();
+    gs.ecs.register::<ProvidesRemoveCurse>();
+    gs.ecs.register::<Charges>();
+    gs.ecs.register::<Wallet>();
+    gs.ecs.register::<GoldPile>();
+    gs.ecs.register::<Vendor>();
+    gs.ecs.register::<Bystander>();
+    gs.ecs.register::<ManaPool>();
+    gs.ecs.register::<KnownSpell>();
+    gs.ecs.register::<TeachesSpell>();
+    gs.ecs.register::<Companion>();
+    gs.ecs.register::<SummonsCompanion>();
+    gs.ecs.register::<Corpse>();
+    gs.ecs.register::<LootTable>();
+    gs.ecs.register::<RangedAttacker>();
+    gs.ecs.register::<Boss>();
+    gs.ecs.register::<Artifact>();
+    gs.ecs.register::<Character>();
+    gs.ecs.register::<Experience>();
     gs.ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
     gs.ecs.insert(rex_assets::RexAssets::new());
 
     gs.ecs.insert(Map::new(1));
     gs.ecs.insert(Point::new(0, 0));
     gs.ecs.insert(RandomNumberGenerator::new());
+    gs.ecs.insert(identification::MagicItemNames::new());
+    gs.ecs.insert(statistics::RunStats::new());
     let player_entity = spawner::player(&mut gs.ecs, 0, 0);
     gs.ecs.insert(player_entity);
 
     gs.ecs.insert(particle_system::ParticleBuilder::new());
-    gs.ecs.insert(gamelog::GameLog {
-        entries: vec!["Good luck...".to_string()],
-    });
+    gs.ecs.insert(effects::EffectQueue::new());
+    let keymap = keybindings::load_keymap();
+    let mut log = gamelog::GameLog::new();
+    for problem in keymap.problems.iter() {
+        log.add(problem);
+    }
+    log.add("Good luck...");
+    gs.ecs.insert(log);
+    gs.ecs.insert(gamelog::LogViewer::default());
+    gs.ecs.insert(keymap);
+    gs.ecs.insert(gui::MouseHover::default());
+    gs.ecs.insert(settings);
+    settings::apply_settings(&gs.ecs);
 
     // initial RunState
     gs.ecs.insert(RunState::MapGeneration {});
-    gs.generate_world_map(1);
+    gs.generate_world_map(0);
 
     rltk::main_loop(context, gs)
 }
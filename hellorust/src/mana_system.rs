@@ -0,0 +1,33 @@
+extern crate specs;
+use crate::components::ManaPool;
+use crate::RunState;
+use specs::prelude::*;
+
+/// How many turns it takes to get one point of mana back.
+pub const MANA_REGEN_TURNS: i32 = 4;
+
+pub struct ManaSystem {}
+
+impl<'a> System<'a> for ManaSystem {
+    type SystemData = (WriteStorage<'a, ManaPool>, ReadExpect<'a, RunState>);
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut mana_pools, runstate) = data;
+
+        // Once per round, on the player's turn
+        if *runstate != RunState::PlayerTurn {
+            return;
+        }
+
+        for pool in (&mut mana_pools).join() {
+            if pool.mana >= pool.max_mana {
+                continue;
+            }
+            pool.regen_timer -= 1;
+            if pool.regen_timer < 1 {
+                pool.mana += 1;
+                pool.regen_timer = MANA_REGEN_TURNS;
+            }
+        }
+    }
+}
@@ -16,6 +16,13 @@ pub enum TileType {
     Wall,
     Floor,
     DownStairs,
+    UpStairs,
+    Road,
+    Grass,
+    /// Can be walked through, but not seen through.
+    Door,
+    /// Bars the way out of a boss arena until the boss is dead. Can be seen through.
+    SealedGate,
 }
 
 #[derive(Default, Serialize, Deserialize, Clone)] // Do we need Clone here?
@@ -72,7 +79,7 @@ impl Map {
     /// "sets blocked for a tile to true if its a wall, false otherwise"
     pub fn populate_blocked(&mut self) {
         for (i, tile) in self.tiles.iter_mut().enumerate() {
-            self.blocked_tiles[i] = *tile == TileType::Wall;
+            self.blocked_tiles[i] = *tile == TileType::Wall || *tile == TileType::SealedGate;
         }
     }
 
@@ -91,7 +98,7 @@ impl Algorithm2D for Map {
 
 impl BaseMap for Map {
     fn is_opaque(&self, idx: usize) -> bool {
-        self.tiles[idx] == TileType::Wall
+        self.tiles[idx] == TileType::Wall || self.tiles[idx] == TileType::Door
     }
 
     fn get_available_exits(&self, idx: usize) -> rltk::SmallVec<[(usize, f32); 10]> {
@@ -152,13 +159,33 @@ pub fn draw_map(map: &Map, ctx: &mut Rltk) {
                     fg = RGB::from_f32(0.0, 0.5, 0.5);
                 }
                 TileType::Wall => {
-                    glyph = wall_glyph(&*map, x, y);
+                    glyph = wall_glyph(map, x, y);
                     fg = RGB::from_f32(0.0, 1.0, 0.0);
                 }
                 TileType::DownStairs => {
                     glyph = rltk::to_cp437('H');
                     fg = brown();
                 }
+                TileType::UpStairs => {
+                    glyph = rltk::to_cp437('<');
+                    fg = brown();
+                }
+                TileType::Road => {
+                    glyph = rltk::to_cp437('·');
+                    fg = grey();
+                }
+                TileType::Grass => {
+                    glyph = rltk::to_cp437('"');
+                    fg = green();
+                }
+                TileType::Door => {
+                    glyph = rltk::to_cp437('+');
+                    fg = brown();
+                }
+                TileType::SealedGate => {
+                    glyph = rltk::to_cp437('≡');
+                    fg = red();
+                }
             }
             if map.bloodstains.contains(&idx) {
                 bg = RGB::from_f32(0.75, 0.0, 0.0);
@@ -184,7 +211,7 @@ pub fn draw_map(map: &Map, ctx: &mut Rltk) {
 // example, a value of 3 means that we have neighbors to the north and
 // south."
 pub fn wall_glyph(map: &Map, x: i32, y: i32) -> u16 {
-    if x < 1 || x > map.width - 2 || y < 1 || y > map.height - 2 as i32 {
+    if x < 1 || x > map.width - 2 || y < 1 || y > map.height - 2_i32 {
         return 35;
     }
     let mut mask: u8 = 0;
@@ -234,5 +261,5 @@ pub fn is_revealed_and_wall(map: &Map, x: i32, y: i32) -> bool {
     // pillars, but introduces ugly walls using only ╠╩╦╣ because most
     // walls have walls behind them since the rooms are 'sliced' out
     // of a solid block
-    return map.tiles[idx] == TileType::Wall && map.revealed_tiles[idx];
+    map.tiles[idx] == TileType::Wall && map.revealed_tiles[idx]
 }
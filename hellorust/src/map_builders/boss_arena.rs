@@ -0,0 +1,122 @@
+use super::{spawner, Map, MapBuilder, Position, TileType, World, FINAL_DEPTH};
+use crate::{boss_system, statistics::RunStats};
+
+/// The arena prefab, drawn the way it ends up on the map:
+/// `@` is where the player comes in, `B` the boss, `!` a health potion,
+/// `=` the sealed gate and `>` the way down behind it.
+/// On the final depth the artifact lies where the stairs would be, and there's a way
+/// back up where the player comes in.
+const ARENA: [&str; 18] = [
+    "#############################################",
+    "#.....#.............................#########",
+    "#.....#.............................#########",
+    "#.....#....##...............##......#########",
+    "#.....#....##...............##......#########",
+    "#.....#.............................#.......#",
+    "#.....#.............................#.......#",
+    "#.!...#.............................#.......#",
+    "#..@.........................B......=...>...#",
+    "#.!...#.............................#.......#",
+    "#.....#.............................#.......#",
+    "#.....#.............................#.......#",
+    "#.....#.............................#########",
+    "#.....#....##...............##......#########",
+    "#.....#....##...............##......#########",
+    "#.....#.............................#########",
+    "#.....#.............................#########",
+    "#############################################",
+];
+
+/// Every fifth depth: no rooms and corridors, just a hand-drawn arena with a boss in it.
+pub struct BossArenaBuilder {
+    map: Map,
+    starting_position: Position,
+    depth: i32,
+    spawns: Vec<(i32, i32, char)>,
+    history: Vec<Map>,
+    record_history: bool,
+}
+
+impl BossArenaBuilder {
+    pub fn new(new_depth: i32, record_history: bool) -> BossArenaBuilder {
+        BossArenaBuilder {
+            map: Map::new(new_depth),
+            starting_position: Position { x: 0, y: 0 },
+            depth: new_depth,
+            spawns: Vec::new(),
+            history: Vec::new(),
+            record_history,
+        }
+    }
+
+    /// Copies the prefab into the middle of the map, remembering what gets spawned where.
+    fn apply_prefab(&mut self) {
+        let offset_x = (self.map.width - ARENA[0].len() as i32) / 2;
+        let offset_y = (self.map.height - ARENA.len() as i32) / 2;
+        let final_depth = self.depth == FINAL_DEPTH;
+        for (dy, row) in ARENA.iter().enumerate() {
+            for (dx, c) in row.chars().enumerate() {
+                let (x, y) = (offset_x + dx as i32, offset_y + dy as i32);
+                let idx = self.map.xy_idx(x, y);
+                self.map.tiles[idx] = match c {
+                    '#' => TileType::Wall,
+                    '=' => TileType::SealedGate,
+                    '>' if !final_depth => TileType::DownStairs,
+                    '@' if final_depth => TileType::UpStairs,
+                    _ => TileType::Floor,
+                };
+                match c {
+                    '@' => self.starting_position = Position { x, y },
+                    'B' | '!' => self.spawns.push((x, y, c)),
+                    '>' if final_depth => self.spawns.push((x, y, c)),
+                    _ => {}
+                }
+            }
+            self.take_snapshot();
+        }
+    }
+}
+
+impl MapBuilder for BossArenaBuilder {
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn build_map(&mut self) {
+        self.apply_prefab();
+    }
+
+    fn spawn_entities(&mut self, ecs: &mut World) {
+        // Back in an arena whose boss is already dead: nobody home, nothing left to take
+        if ecs.fetch::<RunStats>().boss_cleared(self.depth) {
+            boss_system::unseal_gates(&mut ecs.fetch_mut::<Map>());
+            return;
+        }
+        for (x, y, c) in self.spawns.iter() {
+            match c {
+                'B' => spawner::boss(ecs, *x, *y, self.depth),
+                '>' => {
+                    spawner::spawn_entity(ecs, *x, *y, "Heart of the Mountain");
+                }
+                _ => {
+                    spawner::spawn_entity(ecs, *x, *y, "Health Potion");
+                }
+            }
+        }
+    }
+    fn get_snapshot_history(&self) -> Vec<Map> {
+        self.history.clone()
+    }
+    fn take_snapshot(&mut self) {
+        if self.record_history {
+            let mut snapshot = self.map.clone();
+            for v in snapshot.revealed_tiles.iter_mut() {
+                *v = true;
+            }
+            self.history.push(snapshot);
+        }
+    }
+}
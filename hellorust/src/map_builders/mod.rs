@@ -1,6 +1,10 @@
 use super::{spawner, Map, Position, Rect, TileType, World};
 mod simple_map;
 use simple_map::SimpleMapBuilder;
+mod town;
+use town::TownBuilder;
+mod boss_arena;
+use boss_arena::BossArenaBuilder;
 mod common;
 use common::*;
 
@@ -13,6 +17,21 @@ pub trait MapBuilder {
     fn take_snapshot(&mut self);
 }
 
-pub fn random_builder(new_depth: i32) -> Box<dyn MapBuilder> {
-    Box::new(SimpleMapBuilder::new(new_depth))
+/// The artifact lies at the bottom of this one, and there is no way further down.
+pub const FINAL_DEPTH: i32 = 15;
+
+/// Every fifth depth is a boss arena.
+pub fn is_boss_depth(depth: i32) -> bool {
+    depth > 0 && depth % 5 == 0
+}
+
+/// `record_history` keeps a snapshot of every step for the map generation visualizer.
+pub fn random_builder(new_depth: i32, record_history: bool) -> Box<dyn MapBuilder> {
+    if new_depth == 0 {
+        return Box::new(TownBuilder::new(record_history));
+    }
+    if is_boss_depth(new_depth) {
+        return Box::new(BossArenaBuilder::new(new_depth, record_history));
+    }
+    Box::new(SimpleMapBuilder::new(new_depth, record_history))
 }
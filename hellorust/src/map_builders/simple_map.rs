@@ -2,7 +2,6 @@ use super::{
     apply_horizontal_tunnel, apply_room_to_map, apply_vertical_tunnel, spawner, Map, MapBuilder,
     Position, Rect, TileType, World,
 };
-use crate::SHOW_MAPGEN_VISUALIZER;
 use rltk::RandomNumberGenerator;
 
 pub struct SimpleMapBuilder {
@@ -11,16 +10,18 @@ pub struct SimpleMapBuilder {
     depth: i32,
     rooms: Vec<Rect>,
     history: Vec<Map>,
+    record_history: bool,
 }
 
 impl SimpleMapBuilder {
-    pub fn new(new_depth: i32) -> SimpleMapBuilder {
+    pub fn new(new_depth: i32, record_history: bool) -> SimpleMapBuilder {
         SimpleMapBuilder {
             map: Map::new(new_depth),
             starting_position: Position { x: 0, y: 0 },
             depth: new_depth,
             rooms: Vec::new(),
             history: Vec::new(),
+            record_history,
         }
     }
 
@@ -35,8 +36,8 @@ impl SimpleMapBuilder {
             let w = rng.range(MIN_SIZE, MAX_SIZE);
             let h = rng.range(MIN_SIZE, MAX_SIZE);
             //(↓ e.g. max) x = [1..(80-10-1-1)] = [1..68]
-            let x = rng.roll_dice(1, self.map.width as i32 - w - 1) - 1;
-            let y = rng.roll_dice(1, self.map.height as i32 - h - 1) - 1;
+            let x = rng.roll_dice(1, self.map.width - w - 1) - 1;
+            let y = rng.roll_dice(1, self.map.height - h - 1) - 1;
             let new_room = Rect::new(x, y, w, h);
             let mut ok = true;
             for other_room in self.rooms.iter() {
@@ -68,6 +69,11 @@ impl SimpleMapBuilder {
         let stairs_idx = self.map.xy_idx(stairs_position.0, stairs_position.1);
         let start_position = self.rooms[0].center();
         self.map.tiles[stairs_idx] = TileType::DownStairs;
+        // The first level has a way back up to town
+        if self.depth == 1 {
+            let up_idx = self.map.xy_idx(start_position.0, start_position.1);
+            self.map.tiles[up_idx] = TileType::UpStairs;
+        }
         self.starting_position = Position {
             x: start_position.0,
             y: start_position.1,
@@ -96,7 +102,7 @@ impl MapBuilder for SimpleMapBuilder {
         self.history.clone()
     }
     fn take_snapshot(&mut self) {
-        if SHOW_MAPGEN_VISUALIZER {
+        if self.record_history {
             let mut snapshot = self.map.clone();
             for v in snapshot.revealed_tiles.iter_mut() {
                 *v = true;
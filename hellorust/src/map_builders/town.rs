@@ -0,0 +1,162 @@
+use super::{spawner, Map, MapBuilder, Position, Rect, TileType, World};
+use crate::{RandomNumberGenerator, VendorCategory};
+use specs::prelude::*;
+
+/// The main road runs east-west through the middle of town.
+const ROAD_Y: i32 = 21;
+
+#[derive(Clone, Copy)]
+enum Building {
+    Tavern,
+    Shop(VendorCategory),
+    House,
+}
+
+/// The town is laid out by hand: what each building is, and its x, y, width and height.
+/// Doors always face the main road.
+const BUILDINGS: [(Building, i32, i32, i32, i32); 8] = [
+    (Building::Tavern, 4, 6, 16, 10),
+    (Building::Shop(VendorCategory::General), 24, 9, 10, 7),
+    (Building::Shop(VendorCategory::Weaponsmith), 44, 9, 10, 7),
+    (Building::House, 60, 6, 8, 6),
+    (Building::Shop(VendorCategory::Alchemist), 6, 26, 10, 7),
+    (Building::House, 22, 27, 8, 6),
+    (Building::House, 44, 27, 8, 6),
+    (Building::House, 60, 26, 9, 7),
+];
+
+/// Depth 0: a small town on the surface with a tavern, shops and the way down into the dungeon.
+pub struct TownBuilder {
+    map: Map,
+    starting_position: Position,
+    buildings: Vec<(Building, Rect)>,
+    history: Vec<Map>,
+    record_history: bool,
+}
+
+impl TownBuilder {
+    pub fn new(record_history: bool) -> TownBuilder {
+        TownBuilder {
+            map: Map::new(0),
+            starting_position: Position { x: 0, y: 0 },
+            buildings: Vec::new(),
+            history: Vec::new(),
+            record_history,
+        }
+    }
+
+    fn grass_and_walls(&mut self) {
+        for y in 0..self.map.height {
+            for x in 0..self.map.width {
+                let idx = self.map.xy_idx(x, y);
+                if x == 0 || y == 0 || x == self.map.width - 1 || y == self.map.height - 1 {
+                    self.map.tiles[idx] = TileType::Wall;
+                } else {
+                    self.map.tiles[idx] = TileType::Grass;
+                }
+            }
+        }
+        self.take_snapshot();
+    }
+
+    fn main_road(&mut self) {
+        for y in ROAD_Y - 1..=ROAD_Y + 1 {
+            for x in 1..self.map.width - 1 {
+                let idx = self.map.xy_idx(x, y);
+                self.map.tiles[idx] = TileType::Road;
+            }
+        }
+        self.take_snapshot();
+    }
+
+    /// Walls all around, floor inside, and a door facing the main road with a path leading to it.
+    fn add_building(&mut self, building: Building, room: Rect) {
+        for y in room.y1..=room.y2 {
+            for x in room.x1..=room.x2 {
+                let idx = self.map.xy_idx(x, y);
+                if x == room.x1 || x == room.x2 || y == room.y1 || y == room.y2 {
+                    self.map.tiles[idx] = TileType::Wall;
+                } else {
+                    self.map.tiles[idx] = TileType::Floor;
+                }
+            }
+        }
+
+        let door_x = room.center().0;
+        let (door_y, path) = if room.y2 < ROAD_Y {
+            (room.y2, room.y2 + 1..ROAD_Y - 1)
+        } else {
+            (room.y1, ROAD_Y + 2..room.y1)
+        };
+        let door_idx = self.map.xy_idx(door_x, door_y);
+        self.map.tiles[door_idx] = TileType::Door;
+        for y in path {
+            let idx = self.map.xy_idx(door_x, y);
+            self.map.tiles[idx] = TileType::Road;
+        }
+
+        self.buildings.push((building, room));
+        self.take_snapshot();
+    }
+
+    fn dungeon_entrance(&mut self) {
+        let stairs_idx = self.map.xy_idx(self.map.width - 4, ROAD_Y);
+        self.map.tiles[stairs_idx] = TileType::DownStairs;
+        self.starting_position = Position { x: 2, y: ROAD_Y };
+        self.take_snapshot();
+    }
+}
+
+impl MapBuilder for TownBuilder {
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn build_map(&mut self) {
+        self.grass_and_walls();
+        self.main_road();
+        for (building, x, y, w, h) in BUILDINGS.iter() {
+            self.add_building(*building, Rect::new(*x, *y, *w, *h));
+        }
+        self.dungeon_entrance();
+    }
+
+    fn spawn_entities(&mut self, ecs: &mut World) {
+        for (building, room) in self.buildings.iter() {
+            let (x, y) = room.center();
+            match building {
+                Building::Tavern => {
+                    spawner::bystander(ecs, room.x1 + 2, room.y1 + 2, "Barkeep");
+                    let patrons = ecs
+                        .write_resource::<RandomNumberGenerator>()
+                        .roll_dice(1, 3);
+                    for i in 0..patrons {
+                        spawner::bystander(ecs, x - 2 + i * 2, y + 1, "Patron");
+                    }
+                }
+                Building::Shop(category) => {
+                    spawner::vendor(ecs, x, y, *category);
+                }
+                Building::House => spawner::bystander(ecs, x, y, "Townsperson"),
+            }
+        }
+        for x in [20, 38, 56].iter() {
+            spawner::bystander(ecs, *x, ROAD_Y, "Peasant");
+        }
+    }
+    fn get_snapshot_history(&self) -> Vec<Map> {
+        self.history.clone()
+    }
+    fn take_snapshot(&mut self) {
+        if self.record_history {
+            let mut snapshot = self.map.clone();
+            for v in snapshot.revealed_tiles.iter_mut() {
+                *v = true;
+            }
+            self.history.push(snapshot);
+        }
+    }
+}
@@ -1,7 +1,9 @@
 extern crate specs;
 use super::{
-    gamelog::GameLog, CombatStats, DefenseBonus, Equipped, HungerClock, HungerState,
-    MeleePowerBonus, Name, ParticleBuilder, Position, SufferDamage, WantsToMelee,
+    effects::{EffectQueue, EffectType, Targets},
+    gamelog::{GameLog, LogBuilder},
+    CombatStats, DefenseBonus, Equipped, HungerClock, HungerState, MeleePowerBonus, Name,
+    Renderable, WantsToMelee,
 };
 use crate::color::*;
 use specs::prelude::*;
@@ -15,13 +17,12 @@ impl<'a> System<'a> for MeleeCombatSystem {
         WriteStorage<'a, WantsToMelee>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, CombatStats>,
-        WriteStorage<'a, SufferDamage>,
         ReadStorage<'a, MeleePowerBonus>,
         ReadStorage<'a, DefenseBonus>,
         ReadStorage<'a, Equipped>,
-        WriteExpect<'a, ParticleBuilder>,
-        ReadStorage<'a, Position>,
+        WriteExpect<'a, EffectQueue>,
         ReadStorage<'a, HungerClock>,
+        ReadStorage<'a, Renderable>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -31,14 +32,14 @@ impl<'a> System<'a> for MeleeCombatSystem {
             mut wants_melee,
             names,
             combat_stats,
-            mut inflict_damage,
             melee_power_bonuses,
             defense_bonuses,
             equipped,
-            mut particle_builder,
-            positions,
+            mut effects,
             hunger_clock,
+            renderables,
         ) = data;
+        let color_of = |entity: Entity| renderables.get(entity).map_or(white(), |r| r.fg);
 
         for (entity, wants_melee, name, stats) in
             (&entities, &wants_melee, &names, &combat_stats).join()
@@ -73,17 +74,18 @@ impl<'a> System<'a> for MeleeCombatSystem {
                     }
 
                     // spawn 'fighting' particle
-                    let pos = positions.get(wants_melee.target);
-                    if let Some(pos) = pos {
-                        particle_builder.request(
-                            pos.x,
-                            pos.y,
-                            orange(),
-                            black(),
-                            rltk::to_cp437('‼'),
-                            200.0,
-                        );
-                    }
+                    effects.add_effect(
+                        Some(entity),
+                        EffectType::Particle {
+                            glyph: rltk::to_cp437('‼'),
+                            fg: orange(),
+                            bg: black(),
+                            lifespan: 200.0,
+                        },
+                        Targets::Single {
+                            target: wants_melee.target,
+                        },
+                    );
 
                     let damage = i32::max(
                         0,
@@ -91,16 +93,27 @@ impl<'a> System<'a> for MeleeCombatSystem {
                             - (target_stats.defense + defensive_bonus),
                     );
                     if damage == 0 {
-                        log.entries.push(format!(
-                            "{} is unable to hurt {}",
-                            &name.name, &target_name.name
-                        ));
+                        LogBuilder::new()
+                            .name(&name.name, color_of(entity))
+                            .append(" is unable to hurt ")
+                            .name(&target_name.name, color_of(wants_melee.target))
+                            .log(&mut log);
                     } else {
-                        log.entries.push(format!(
-                            "{} hits {} for {} hp.",
-                            &name.name, &target_name.name, damage
-                        ));
-                        SufferDamage::new_damage(&mut inflict_damage, wants_melee.target, damage)
+                        LogBuilder::new()
+                            .name(&name.name, color_of(entity))
+                            .append(" hits ")
+                            .name(&target_name.name, color_of(wants_melee.target))
+                            .append(" for ")
+                            .damage(damage)
+                            .append(" hp.")
+                            .log(&mut log);
+                        effects.add_effect(
+                            Some(entity),
+                            EffectType::Damage { amount: damage },
+                            Targets::Single {
+                                target: wants_melee.target,
+                            },
+                        );
                     }
                 }
             }
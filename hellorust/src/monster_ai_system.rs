@@ -1,7 +1,7 @@
 extern crate specs;
 use super::{
-    Confusion, EntityMoved, Map, Monster, Name, ParticleBuilder, Point, Position, RunState,
-    Viewshed, WantsToMelee,
+    Companion, Confusion, EntityMoved, Map, Monster, Name, ParticleBuilder, Point, Position,
+    RangedAttacker, RunState, Viewshed, WantsToMelee, WantsToPickupItem, WantsToUseItem,
 };
 use crate::color::*;
 use specs::prelude::*;
@@ -25,6 +25,10 @@ impl<'a> System<'a> for MonsterAI {
         WriteStorage<'a, Confusion>,
         WriteExpect<'a, ParticleBuilder>,
         WriteStorage<'a, EntityMoved>,
+        ReadStorage<'a, Companion>,
+        ReadStorage<'a, WantsToUseItem>,
+        ReadStorage<'a, WantsToPickupItem>,
+        ReadStorage<'a, RangedAttacker>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -42,6 +46,10 @@ impl<'a> System<'a> for MonsterAI {
             mut confused,
             mut particle_builder,
             mut entity_moved,
+            companions,
+            wants_use,
+            wants_pickup,
+            ranged_attackers,
         ) = data;
 
         // only run system if the state is MonsterTurn
@@ -53,10 +61,18 @@ impl<'a> System<'a> for MonsterAI {
         // we've explicitly included names in the AI's join, so we better be sure that the player has one!
         // Otherwise, the AI will ignore the player altogether." - Chapter 6
 
-        for (entity, mut viewshed, _monster, _name, mut pos) in
+        let companion_positions: Vec<(Entity, Point)> = (&entities, &companions, &position)
+            .join()
+            .map(|(entity, _companion, pos)| (entity, Point::new(pos.x, pos.y)))
+            .collect();
+
+        for (entity, viewshed, _monster, _name, pos) in
             (&entities, &mut viewshed, &monster, &name, &mut position).join()
         {
-            let mut can_act = true;
+            // Already busy with an item this turn (see MonsterItemAI)
+            let mut can_act = wants_use.get(entity).is_none()
+                && wants_pickup.get(entity).is_none()
+                && entity_moved.get(entity).is_none();
             let is_confused = confused.get_mut(entity);
 
             match is_confused {
@@ -92,11 +108,26 @@ impl<'a> System<'a> for MonsterAI {
                         )
                         .expect("Unable to insert attack");
                     return;
-                } else if viewshed.visible_tiles.contains(&*player_pos) {
+                }
+
+                // Fight back against the player's companions when they get close
+                let adjacent_companion = companion_positions.iter().find(|(_companion, c_pos)| {
+                    rltk::DistanceAlg::Pythagoras.distance2d(Point::new(pos.x, pos.y), *c_pos) < 1.5
+                });
+                if let Some((companion, _c_pos)) = adjacent_companion {
+                    wants_to_melee
+                        .insert(entity, WantsToMelee { target: *companion })
+                        .expect("Unable to insert attack");
+                } else if viewshed.visible_tiles.contains(&*player_pos)
+                    // archers and casters hold their ground once the player is in range
+                    && !ranged_attackers
+                        .get(entity)
+                        .is_some_and(|ranged| distance <= ranged.range as f32)
+                {
                     let path = rltk::a_star_search(
                         map.xy_idx(pos.x, pos.y) as i32,
                         map.xy_idx(player_pos.x, player_pos.y) as i32,
-                        &mut *map,
+                        &*map,
                     );
 
                     if path.success && path.steps.len() > 1 {
@@ -0,0 +1,298 @@
+extern crate specs;
+use super::{
+    gamelog::GameLog,
+    identification::{display_name, MagicItemNames},
+    inventory_system::equipment_in_the_way,
+    AreaOfEffect, Artifact, Charges, CombatStats, Confusion, Corpse, CursedItem, DefenseBonus,
+    EntityMoved, Equippable, Equipped, GoldPile, InBackpack, InflictsDamage, Item, Map,
+    MeleePowerBonus, Monster, Name, ObfuscatedName, Point, Position, ProvidesHealing, Ranged,
+    RunState, Viewshed, WantsToPickupItem, WantsToUseItem,
+};
+use specs::prelude::*;
+extern crate rltk;
+
+/// Monsters that know what to do with their stuff: they drink potions when hurt, read
+/// scrolls at the player, put on better gear, and go grab whatever they see lying around.
+/// Runs before `MonsterAI`, which leaves alone any monster that already acted here.
+pub struct MonsterItemAI {}
+
+impl<'a> System<'a> for MonsterItemAI {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        (
+            WriteExpect<'a, Map>,
+            ReadExpect<'a, Point>,
+            ReadExpect<'a, RunState>,
+            WriteExpect<'a, GameLog>,
+            ReadExpect<'a, MagicItemNames>,
+            Entities<'a>,
+        ),
+        (
+            WriteStorage<'a, Viewshed>,
+            ReadStorage<'a, Monster>,
+            ReadStorage<'a, Confusion>,
+            ReadStorage<'a, CombatStats>,
+            WriteStorage<'a, Position>,
+            WriteStorage<'a, EntityMoved>,
+            ReadStorage<'a, Name>,
+            ReadStorage<'a, ObfuscatedName>,
+        ),
+        (
+            ReadStorage<'a, Item>,
+            ReadStorage<'a, Corpse>,
+            ReadStorage<'a, InBackpack>,
+            ReadStorage<'a, Equippable>,
+            ReadStorage<'a, Equipped>,
+            ReadStorage<'a, MeleePowerBonus>,
+            ReadStorage<'a, DefenseBonus>,
+            ReadStorage<'a, ProvidesHealing>,
+            ReadStorage<'a, Ranged>,
+            ReadStorage<'a, InflictsDamage>,
+            ReadStorage<'a, AreaOfEffect>,
+            ReadStorage<'a, Charges>,
+            ReadStorage<'a, CursedItem>,
+            ReadStorage<'a, Artifact>,
+            ReadStorage<'a, GoldPile>,
+        ),
+        (
+            WriteStorage<'a, WantsToUseItem>,
+            WriteStorage<'a, WantsToPickupItem>,
+        ),
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            (mut map, player_pos, runstate, mut log, magic_item_names, entities),
+            (
+                mut viewsheds,
+                monsters,
+                confused,
+                combat_stats,
+                mut positions,
+                mut entity_moved,
+                names,
+                obfuscated_names,
+            ),
+            (
+                items,
+                corpses,
+                backpack,
+                equippable,
+                equipped,
+                power_bonuses,
+                defense_bonuses,
+                healing,
+                ranged,
+                inflicts_damage,
+                aoe,
+                charges,
+                cursed,
+                artifacts,
+                gold_piles,
+            ),
+            (mut wants_use, mut wants_pickup),
+        ) = data;
+
+        // only run system if the state is MonsterTurn
+        if *runstate != RunState::MonsterTurn {
+            return;
+        }
+
+        // Monsters leave corpses, gold and the artifact where they are
+        let floor_items: Vec<(Entity, Point)> = (
+            &entities,
+            &items,
+            &positions,
+            !&corpses,
+            !&gold_piles,
+            !&artifacts,
+        )
+            .join()
+            .map(|(entity, _item, pos, ..)| (entity, Point::new(pos.x, pos.y)))
+            .collect();
+
+        for (entity, viewshed, _monster, stats, pos) in (
+            &entities,
+            &mut viewsheds,
+            &monsters,
+            &combat_stats,
+            &mut positions,
+        )
+            .join()
+        {
+            if confused.get(entity).is_some() {
+                continue;
+            }
+            let here = Point::new(pos.x, pos.y);
+            let seen_by_player = map.visible_tiles[map.xy_idx(pos.x, pos.y)];
+            let monster_name = &names.get(entity).unwrap().name;
+            let sees_player = viewshed.visible_tiles.contains(&*player_pos);
+            let distance_to_player = rltk::DistanceAlg::Pythagoras.distance2d(here, *player_pos);
+
+            let carried: Vec<Entity> = (&entities, &backpack)
+                .join()
+                .filter(|(_item, pack)| pack.owner == entity)
+                .map(|(item, _pack)| item)
+                .filter(|item| charges.get(*item).is_none_or(|c| c.charges > 0))
+                .collect();
+            let item_name =
+                |item: Entity| display_name(item, &names, &obfuscated_names, &magic_item_names);
+
+            // Drink a healing potion when badly hurt
+            if stats.hp < stats.max_hp / 2 {
+                if let Some(potion) = carried.iter().find(|item| healing.get(**item).is_some()) {
+                    wants_use
+                        .insert(
+                            entity,
+                            WantsToUseItem {
+                                item: *potion,
+                                target: None,
+                            },
+                        )
+                        .expect("Unable to insert use item intent");
+                    if seen_by_player {
+                        log.add(format!(
+                            "The {} drinks a {}.",
+                            monster_name,
+                            item_name(*potion)
+                        ));
+                    }
+                    continue;
+                }
+            }
+
+            // Read offensive scrolls (or zap wands) at the player from a distance
+            if sees_player && distance_to_player >= 1.5 {
+                let attack = carried.iter().find(|item| {
+                    let in_range = ranged
+                        .get(**item)
+                        .is_some_and(|r| distance_to_player <= r.range as f32);
+                    let offensive =
+                        inflicts_damage.get(**item).is_some() || confused.get(**item).is_some();
+                    // don't stand in your own fireball
+                    let safe = aoe
+                        .get(**item)
+                        .is_none_or(|a| distance_to_player > a.radius as f32);
+                    in_range && offensive && safe
+                });
+                if let Some(attack) = attack {
+                    wants_use
+                        .insert(
+                            entity,
+                            WantsToUseItem {
+                                item: *attack,
+                                target: Some(*player_pos),
+                            },
+                        )
+                        .expect("Unable to insert use item intent");
+                    log.add(format!(
+                        "The {} uses a {} on you!",
+                        monster_name,
+                        item_name(*attack)
+                    ));
+                    continue;
+                }
+            }
+
+            // Leave the fighting to MonsterAI
+            if distance_to_player < 1.5 {
+                continue;
+            }
+
+            // Chasing the player beats shopping
+            if sees_player {
+                continue;
+            }
+
+            // Put on anything better than everything it would replace. Cursed gear
+            // can't be taken off, so whatever it's in the way of stays put.
+            let worth = |item: Entity| {
+                power_bonuses.get(item).map_or(0, |b| b.power)
+                    + defense_bonuses.get(item).map_or(0, |b| b.defense)
+            };
+            let upgrade = carried.iter().find(|item| match equippable.get(**item) {
+                None => false,
+                Some(can_equip) => {
+                    let in_the_way = equipment_in_the_way(
+                        &entities,
+                        &equipped,
+                        &equippable,
+                        &cursed,
+                        entity,
+                        can_equip,
+                    );
+                    !in_the_way.iter().any(|worn| cursed.get(*worn).is_some())
+                        && worth(**item) > in_the_way.iter().map(|worn| worth(*worn)).sum()
+                }
+            });
+            if let Some(upgrade) = upgrade {
+                wants_use
+                    .insert(
+                        entity,
+                        WantsToUseItem {
+                            item: *upgrade,
+                            target: None,
+                        },
+                    )
+                    .expect("Unable to insert use item intent");
+                if seen_by_player {
+                    log.add(format!(
+                        "The {} equips a {}.",
+                        monster_name,
+                        item_name(*upgrade)
+                    ));
+                }
+                continue;
+            }
+
+            // Pick up what's underfoot, or head for the nearest thing in sight
+            if let Some((item, _item_pos)) = floor_items.iter().find(|(_item, p)| *p == here) {
+                wants_pickup
+                    .insert(
+                        entity,
+                        WantsToPickupItem {
+                            collected_by: entity,
+                            item: *item,
+                        },
+                    )
+                    .expect("Unable to insert WantsToPickupItem");
+                if seen_by_player {
+                    log.add(format!(
+                        "The {} picks up a {}.",
+                        monster_name,
+                        item_name(*item)
+                    ));
+                }
+                continue;
+            }
+            let nearest_item = floor_items
+                .iter()
+                .filter(|(_item, p)| viewshed.visible_tiles.contains(p))
+                .min_by(|a, b| {
+                    rltk::DistanceAlg::Pythagoras
+                        .distance2d(here, a.1)
+                        .partial_cmp(&rltk::DistanceAlg::Pythagoras.distance2d(here, b.1))
+                        .unwrap()
+                });
+            if let Some((_item, item_pos)) = nearest_item {
+                let idx = map.xy_idx(pos.x, pos.y);
+                let path = rltk::a_star_search(
+                    idx as i32,
+                    map.xy_idx(item_pos.x, item_pos.y) as i32,
+                    &*map,
+                );
+                if path.success && path.steps.len() > 1 {
+                    map.blocked_tiles[idx] = false;
+                    pos.x = path.steps[1] as i32 % map.width;
+                    pos.y = path.steps[1] as i32 / map.width;
+                    entity_moved
+                        .insert(entity, EntityMoved {})
+                        .expect("Unable to insert marker");
+                    let new_idx = map.xy_idx(pos.x, pos.y);
+                    map.blocked_tiles[new_idx] = true;
+                    viewshed.dirty = true;
+                }
+            }
+        }
+    }
+}
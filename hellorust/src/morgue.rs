@@ -0,0 +1,189 @@
+// A plain text record of a character's death, in the spirit of the
+// morgue files of DCSS and friends, for sharing and talking about runs.
+use super::{
+    character_creation::{class_name, race_name},
+    gamelog::GameLog,
+    gui::{backpack_items, paper_doll},
+    identification::item_display_name,
+    statistics::{current_score, RunStats},
+    trade, Character, CombatStats, Hidden, HungerClock, HungerState, ManaPool, Map, Name, Position,
+    Renderable, TileType,
+};
+use specs::prelude::*;
+use std::fmt::Write;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How many of the last log lines make it into the morgue file.
+const MORGUE_LOG_LINES: usize = 20;
+
+// There's no file system (or clock) to write a morgue file with on the web,
+// so like save_game this is a stub there.
+#[cfg(target_arch = "wasm32")]
+pub fn write_morgue(_ecs: &World) {}
+
+/// Writes `morgue-<timestamp>.txt` next to the save game. Called while the dead player
+/// is still around, before `game_over_cleanup` wipes everything.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_morgue(ecs: &World) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs());
+    let filename = format!("./morgue-{}.txt", timestamp);
+    // Not being able to write the file shouldn't take the game down with it
+    let _ = std::fs::write(filename, morgue_text(ecs));
+}
+
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+fn morgue_text(ecs: &World) -> String {
+    let mut text = String::new();
+    let player_entity = *ecs.fetch::<Entity>();
+    let stats = ecs.fetch::<RunStats>();
+    let map = ecs.fetch::<Map>();
+
+    let _ = writeln!(text, "Hello Rust World morgue file");
+    let _ = writeln!(text, "============================");
+    let _ = writeln!(
+        text,
+        "Killed by {} on depth {}.",
+        stats
+            .cause_of_death
+            .clone()
+            .unwrap_or_else(|| "something unseen".to_string()),
+        map.depth
+    );
+    let _ = writeln!(
+        text,
+        "Score {} after {} turns, deepest depth {}.",
+        current_score(ecs),
+        stats.turns,
+        stats.deepest_depth
+    );
+
+    section(&mut text, "Character");
+    if let (Some(name), Some(character)) = (
+        ecs.read_storage::<Name>().get(player_entity),
+        ecs.read_storage::<Character>().get(player_entity),
+    ) {
+        let _ = writeln!(
+            text,
+            "{}, {} {}",
+            name.name,
+            race_name(character.race),
+            class_name(character.class)
+        );
+    }
+    if let Some(combat) = ecs.read_storage::<CombatStats>().get(player_entity) {
+        let _ = writeln!(
+            text,
+            "HP {}/{}  Strength {}  Defense {}",
+            combat.hp, combat.max_hp, combat.strength, combat.defense
+        );
+    }
+    if let Some(mana) = ecs.read_storage::<ManaPool>().get(player_entity) {
+        let _ = writeln!(text, "MP {}/{}", mana.mana, mana.max_mana);
+    }
+    let _ = writeln!(text, "Gold {}", trade::gold(ecs, player_entity));
+    if let Some(clock) = ecs.read_storage::<HungerClock>().get(player_entity) {
+        let hunger = match clock.state {
+            HungerState::WellFed => "Well Fed",
+            HungerState::Normal => "Normal",
+            HungerState::Hungry => "Hungry",
+            HungerState::Starving => "Starving",
+        };
+        let _ = writeln!(text, "Hunger: {}", hunger);
+    }
+
+    section(&mut text, "Equipment");
+    for (label, item) in paper_doll(ecs, player_entity) {
+        let item_name = item.map_or("-".to_string(), |item| item_display_name(ecs, item));
+        let _ = writeln!(text, "{:<11} {}", format!("{}:", label), item_name);
+    }
+
+    section(&mut text, "Inventory");
+    let items = backpack_items(ecs);
+    if items.is_empty() {
+        let _ = writeln!(text, "(nothing)");
+    }
+    for (_item, name) in items {
+        let _ = writeln!(text, "{}", name);
+    }
+
+    section(&mut text, "Kills");
+    if stats.kills.is_empty() {
+        let _ = writeln!(text, "(none)");
+    }
+    for (name, count) in stats.kills.iter() {
+        let _ = writeln!(text, "{:>4} {}", count, name);
+    }
+    let _ = writeln!(
+        text,
+        "Damage dealt {}, damage taken {}, items used {}.",
+        stats.damage_dealt, stats.damage_taken, stats.items_used
+    );
+
+    section(&mut text, "Last messages");
+    let log = ecs.fetch::<GameLog>();
+    let first = log.entries.len().saturating_sub(MORGUE_LOG_LINES);
+    for entry in log.entries.iter().skip(first) {
+        let _ = writeln!(text, "[turn {:>5}] {}", entry.turn, entry.text());
+    }
+
+    section(&mut text, "Map");
+    for line in ascii_map(ecs) {
+        let _ = writeln!(text, "{}", line.trim_end());
+    }
+
+    text
+}
+
+fn section(text: &mut String, title: &str) {
+    let _ = writeln!(text);
+    let _ = writeln!(text, "{}", title);
+    let _ = writeln!(text, "{}", "-".repeat(title.len()));
+}
+
+/// The map the way the player remembers it, with whatever was in sight at the end.
+fn ascii_map(ecs: &World) -> Vec<String> {
+    let map = ecs.fetch::<Map>();
+    let mut chars: Vec<char> = map
+        .tiles
+        .iter()
+        .enumerate()
+        .map(|(idx, tile)| {
+            if !map.revealed_tiles[idx] {
+                return ' ';
+            }
+            match tile {
+                TileType::Wall => '#',
+                TileType::Floor => '.',
+                TileType::DownStairs => '>',
+                TileType::UpStairs => '<',
+                TileType::Road => ':',
+                TileType::Grass => '"',
+                TileType::Door => '+',
+                TileType::SealedGate => '=',
+            }
+        })
+        .collect();
+
+    let positions = ecs.read_storage::<Position>();
+    let renderables = ecs.read_storage::<Renderable>();
+    let hidden = ecs.read_storage::<Hidden>();
+    let mut visible = (&positions, &renderables, !&hidden)
+        .join()
+        .collect::<Vec<_>>();
+    // same as on screen: lower render order ends up on top
+    visible.sort_by_key(|&a| std::cmp::Reverse(a.1.render_order));
+    for (pos, render, _hidden) in visible {
+        let idx = map.xy_idx(pos.x, pos.y);
+        if map.visible_tiles[idx] {
+            chars[idx] = rltk::to_char(render.glyph as u8);
+        }
+    }
+
+    chars
+        .chunks(map.width as usize)
+        .map(|row| row.iter().collect())
+        .collect()
+}
@@ -1,12 +1,45 @@
 use super::{
-    gamelog::GameLog, CombatStats, EntityMoved, HungerClock, HungerState, Item, Map, Monster,
-    Player, Point, Position, RunState, State, TileType, Viewshed, WantsToMelee, WantsToPickupItem,
+    gamelog::{GameLog, LogViewer},
+    CombatStats, Companion, Confusion, EntityMoved, HungerClock, HungerState, Item, KnownSpell,
+    ManaPool, Map, Monster, Name, Player, Point, Position, RandomNumberGenerator, Ranged, RunState,
+    State, TileType, Vendor, Viewshed, WantsToMelee, WantsToPickupItem, WantsToUseItem,
 };
-use rltk::{Rltk, VirtualKeyCode};
+use crate::gui::TradeMode;
+use crate::keybindings::{Command, Keymap};
+use crate::statistics::RunStats;
+use rltk::Rltk;
 use specs::prelude::*;
-use std::cmp::{max, min};
 
-pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
+/// A confused player doesn't quite end up going where they meant to.
+/// The confusion itself wears off in ConfusionSystem, however the turn is spent.
+fn stumble_if_confused(delta_x: i32, delta_y: i32, ecs: &mut World) -> (i32, i32) {
+    let player_entity = *ecs.fetch::<Entity>();
+    if !ecs.read_storage::<Confusion>().contains(player_entity) {
+        return (delta_x, delta_y);
+    }
+    let directions = [
+        (-1, -1),
+        (0, -1),
+        (1, -1),
+        (-1, 0),
+        (1, 0),
+        (-1, 1),
+        (0, 1),
+        (1, 1),
+    ];
+    let stumble = directions[(ecs
+        .write_resource::<RandomNumberGenerator>()
+        .roll_dice(1, 8)
+        - 1) as usize];
+    if stumble != (delta_x, delta_y) {
+        ecs.write_resource::<GameLog>()
+            .add("You stumble around in confusion.".to_string());
+    }
+    stumble
+}
+
+pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState {
+    let (delta_x, delta_y) = stumble_if_confused(delta_x, delta_y, ecs);
     let mut positions = ecs.write_storage::<Position>();
     let mut players = ecs.write_storage::<Player>();
     let mut viewsheds = ecs.write_storage::<Viewshed>();
@@ -15,6 +48,10 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
     let _map = ecs.fetch::<Map>();
     let mut wants_to_melee = ecs.write_storage::<WantsToMelee>();
     let mut entity_moved = ecs.write_storage::<EntityMoved>();
+    let vendors = ecs.read_storage::<Vendor>();
+    let companions = ecs.read_storage::<Companion>();
+    let mut swap_with: Option<Entity> = None;
+    let mut old_pos: Option<(i32, i32)> = None;
 
     let map = ecs.fetch::<Map>();
 
@@ -23,8 +60,29 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
     {
         let destination_idx = map.xy_idx(pos.x + delta_x, pos.y + delta_y);
 
+        // Bumping into a vendor opens up shop
+        for potential_target in map.tile_content[destination_idx].iter() {
+            if vendors.get(*potential_target).is_some() {
+                return RunState::ShowTrade {
+                    vendor: *potential_target,
+                    mode: TradeMode::Buy,
+                    page: 0,
+                };
+            }
+        }
+
+        // Walking into a companion trades places with it
+        for potential_target in map.tile_content[destination_idx].iter() {
+            if companions.get(*potential_target).is_some() {
+                swap_with = Some(*potential_target);
+            }
+        }
+
         //"Bump to attack (walking into the target)"
         for potential_target in map.tile_content[destination_idx].iter() {
+            if swap_with.is_some() {
+                break;
+            }
             let target = combat_stats.get(*potential_target);
             match target {
                 None => {}
@@ -38,14 +96,15 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
                             },
                         )
                         .expect("Add target failed");
-                    return; // So we don't move after attacking
+                    return RunState::PlayerTurn; // So we don't move after attacking
                 }
             }
         }
 
-        if !map.blocked_tiles[destination_idx] {
-            pos.x = min(79, max(0, pos.x + delta_x));
-            pos.y = min(49, max(0, pos.y + delta_y));
+        if !map.blocked_tiles[destination_idx] || swap_with.is_some() {
+            old_pos = Some((pos.x, pos.y));
+            pos.x = (pos.x + delta_x).clamp(0, 79);
+            pos.y = (pos.y + delta_y).clamp(0, 49);
             entity_moved
                 .insert(entity, EntityMoved {})
                 .expect("Unable to insert marker");
@@ -58,6 +117,20 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
             player_pos.y = pos.y;
         }
     }
+
+    if let (Some(companion), Some((x, y))) = (swap_with, old_pos) {
+        if let Some(companion_pos) = positions.get_mut(companion) {
+            companion_pos.x = x;
+            companion_pos.y = y;
+        }
+        if let Some(companion_viewshed) = viewsheds.get_mut(companion) {
+            companion_viewshed.dirty = true;
+        }
+        entity_moved
+            .insert(companion, EntityMoved {})
+            .expect("Unable to insert marker");
+    }
+    RunState::PlayerTurn
 }
 
 fn get_item(ecs: &mut World) {
@@ -76,7 +149,7 @@ fn get_item(ecs: &mut World) {
     }
 
     match target_item {
-        None => gamelog.entries.push(String::from("Nothing to pick up")),
+        None => gamelog.add(String::from("Nothing to pick up")),
         Some(item) => {
             let mut pickup = ecs.write_storage::<WantsToPickupItem>();
             pickup
@@ -84,7 +157,7 @@ fn get_item(ecs: &mut World) {
                     *player_entity,
                     WantsToPickupItem {
                         collected_by: *player_entity,
-                        item: item,
+                        item,
                     },
                 )
                 .expect("Unable to insert WantsToPickupItem");
@@ -93,48 +166,63 @@ fn get_item(ecs: &mut World) {
 }
 
 pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
-    // Player movement
-    match ctx.key {
+    let command = match ctx.key {
         None => return RunState::AwaitingInput, // Nothing happened, don't Tick yet.
-        Some(key) => match key {
-            VirtualKeyCode::Period => {
-                if try_next_level(&mut gs.ecs) {
-                    return RunState::NextLevel;
-                }
-            }
-            VirtualKeyCode::O => return RunState::ShowDropItem,
-            VirtualKeyCode::I => return RunState::ShowInventory,
-            VirtualKeyCode::G => get_item(&mut gs.ecs),
-            VirtualKeyCode::R => return RunState::ShowRemoveItem,
-            // Skip turn
-            VirtualKeyCode::Numpad5 => return skip_turn(&mut gs.ecs),
-            VirtualKeyCode::Space => return skip_turn(&mut gs.ecs),
-            // Cardinal movement
-            VirtualKeyCode::Left | VirtualKeyCode::A | VirtualKeyCode::Numpad4 => {
-                try_move_player(-1, 0, &mut gs.ecs)
-            }
-            VirtualKeyCode::Right | VirtualKeyCode::D | VirtualKeyCode::Numpad6 => {
-                try_move_player(1, 0, &mut gs.ecs)
+        Some(key) => gs.ecs.fetch::<Keymap>().command_for(key),
+    };
+    let command = match command {
+        // Nothing we know, do nothing
+        None => return RunState::AwaitingInput,
+        Some(command) => command,
+    };
+    if let Some((delta_x, delta_y)) = command.direction() {
+        return try_move_player(delta_x, delta_y, &mut gs.ecs);
+    }
+    match command {
+        Command::Descend => {
+            if try_next_level(&mut gs.ecs) {
+                return RunState::NextLevel;
             }
-            VirtualKeyCode::Up | VirtualKeyCode::W | VirtualKeyCode::Numpad8 => {
-                try_move_player(0, -1, &mut gs.ecs)
+        }
+        Command::Ascend => {
+            if try_previous_level(&mut gs.ecs) {
+                return RunState::PreviousLevel;
             }
-            VirtualKeyCode::Down | VirtualKeyCode::S | VirtualKeyCode::Numpad2 => {
-                try_move_player(0, 1, &mut gs.ecs)
+        }
+        Command::Drop => return RunState::ShowDropItem { page: 0 },
+        Command::Inventory => return RunState::ShowInventory { page: 0 },
+        Command::PickUp => get_item(&mut gs.ecs),
+        Command::Equipment => return RunState::ShowEquipment,
+        Command::CharacterSheet => return RunState::ShowCharacterSheet,
+        Command::Look => {
+            let player_pos = *gs.ecs.fetch::<Point>();
+            return RunState::LookMode {
+                x: player_pos.x,
+                y: player_pos.y,
+            };
+        }
+        Command::MessageLog => {
+            *gs.ecs.write_resource::<LogViewer>() = LogViewer::default();
+            return RunState::ShowLog;
+        }
+        Command::Options => {
+            return RunState::ShowOptions {
+                selected: 0,
+                from_main_menu: false,
             }
-            // Diagonal movement
-            VirtualKeyCode::Q | VirtualKeyCode::Numpad7 => try_move_player(-1, -1, &mut gs.ecs),
-            VirtualKeyCode::E | VirtualKeyCode::Numpad9 => try_move_player(1, -1, &mut gs.ecs),
-            VirtualKeyCode::Z | VirtualKeyCode::Numpad1 => try_move_player(-1, 1, &mut gs.ecs),
-            VirtualKeyCode::C | VirtualKeyCode::Numpad3 => try_move_player(1, 1, &mut gs.ecs),
-            // Saving
-            VirtualKeyCode::Escape => return RunState::SaveGame,
-            // Nothing pressed, do nothing
-            _ => return RunState::AwaitingInput,
-        },
+        }
+        Command::Help => return RunState::ShowHelp,
+        // Spell hotbar
+        Command::CastSpell(slot) => return cast_spell(&mut gs.ecs, slot),
+        // Skip turn
+        Command::Wait => return skip_turn(&mut gs.ecs),
+        // Saving
+        Command::SaveAndQuit => return RunState::SaveGame,
+        // Movement is taken care of above
+        _ => return RunState::AwaitingInput,
     }
     // If a button was pressed, the next Tick may occur.
-    return RunState::PlayerTurn;
+    RunState::PlayerTurn
 }
 
 fn skip_turn(ecs: &mut World) -> RunState {
@@ -178,7 +266,7 @@ fn skip_turn(ecs: &mut World) -> RunState {
         player_health.hp = i32::min(player_health.hp + 1, player_health.max_hp);
     }
 
-    return RunState::PlayerTurn;
+    RunState::PlayerTurn
 }
 
 pub fn try_next_level(ecs: &mut World) -> bool {
@@ -189,9 +277,77 @@ pub fn try_next_level(ecs: &mut World) -> bool {
         true
     } else {
         let mut gamelog = ecs.fetch_mut::<GameLog>();
-        gamelog
-            .entries
-            .push("There is no way down from here.".to_string());
+        gamelog.add("There is no way down from here.".to_string());
+        false
+    }
+}
+
+pub fn try_previous_level(ecs: &mut World) -> bool {
+    let player_pos = ecs.fetch::<Point>();
+    let map = ecs.fetch::<Map>();
+    let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+    if map.tiles[player_idx] == TileType::UpStairs {
+        true
+    } else {
+        let mut gamelog = ecs.fetch_mut::<GameLog>();
+        gamelog.add("There is no way up from here.".to_string());
         false
     }
 }
+
+/// Whether the player has picked up the artifact, and so is on the way back up.
+pub fn artifact_recovered(ecs: &World) -> bool {
+    ecs.fetch::<RunStats>().artifact_recovered
+}
+
+/// The player's spells in hotbar order, with their names and mana costs.
+pub fn known_spells(ecs: &World) -> Vec<(Entity, String, i32)> {
+    let player_entity = ecs.fetch::<Entity>();
+    let entities = ecs.entities();
+    let known_spells = ecs.read_storage::<KnownSpell>();
+    let names = ecs.read_storage::<Name>();
+    (&entities, &known_spells, &names)
+        .join()
+        .filter(|(_entity, spell, _name)| spell.owner == *player_entity)
+        .map(|(entity, spell, name)| (entity, name.name.clone(), spell.mana_cost))
+        .collect()
+}
+
+/// Casting goes through the same motions as reading the matching scroll:
+/// pick a target if it needs one, then "use" the spell.
+fn cast_spell(ecs: &mut World, slot: usize) -> RunState {
+    let (spell, spell_name, mana_cost) = match known_spells(ecs).get(slot) {
+        Some(spell) => spell.clone(),
+        None => return RunState::AwaitingInput,
+    };
+    let player_entity = *ecs.fetch::<Entity>();
+    let mana = ecs
+        .read_storage::<ManaPool>()
+        .get(player_entity)
+        .map_or(0, |pool| pool.mana);
+    if mana < mana_cost {
+        ecs.fetch_mut::<GameLog>().add(format!(
+            "You don't have enough mana to cast {}.",
+            spell_name
+        ));
+        return RunState::AwaitingInput;
+    }
+
+    if let Some(ranged) = ecs.read_storage::<Ranged>().get(spell) {
+        return RunState::ShowTargeting {
+            range: ranged.range,
+            item: spell,
+            cursor: None,
+        };
+    }
+    ecs.write_storage::<WantsToUseItem>()
+        .insert(
+            player_entity,
+            WantsToUseItem {
+                item: spell,
+                target: None,
+            },
+        )
+        .expect("Unable to insert use item intent");
+    RunState::PlayerTurn
+}
@@ -34,7 +34,7 @@ impl RandomTable {
             self.entries
                 .push(RandomEntry::new(name.to_string(), weight));
         }
-        return self;
+        self
     }
 
     pub fn roll(&self, rng: &mut RandomNumberGenerator) -> String {
@@ -42,21 +42,55 @@ impl RandomTable {
             return "None".to_string();
         }
         let mut roll = rng.roll_dice(1, self.total_weight) - 1;
-        let mut index: usize = 0;
 
         // "If the roll is below the weight, it returns it - otherwise, it
         // reduces the roll by the weight and tests the next entry.
         // This gives a chance equal to the relative weight of the
         // entry for any given item in the table."
-        while roll > 0 {
-            if roll < self.entries[index].weight {
-                return self.entries[index].name.clone();
+        // A roll of 0 picks the first entry too.
+        for entry in self.entries.iter() {
+            if roll < entry.weight {
+                return entry.name.clone();
             }
 
-            roll -= self.entries[index].weight;
-            index += 1;
+            roll -= entry.weight;
         }
 
         "None".to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_table_with_one_entry_always_rolls_it() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let table = RandomTable::new().add("Cleaver", 1);
+        for _ in 0..100 {
+            assert_eq!(table.roll(&mut rng), "Cleaver");
+        }
+    }
+
+    #[test]
+    fn every_entry_can_come_up() {
+        let mut rng = RandomNumberGenerator::seeded(2);
+        let table = RandomTable::new()
+            .add("Goblin", 1)
+            .add("Orc", 1)
+            .add("Ogre", 1);
+        let rolls: Vec<String> = (0..300).map(|_| table.roll(&mut rng)).collect();
+        for name in ["Goblin", "Orc", "Ogre"].iter() {
+            assert!(rolls.iter().any(|roll| roll == name));
+        }
+        assert!(rolls.iter().all(|roll| roll != "None"));
+    }
+
+    #[test]
+    fn an_empty_table_rolls_none() {
+        let mut rng = RandomNumberGenerator::seeded(3);
+        let table = RandomTable::new().add("Nothing", 0);
+        assert_eq!(table.roll(&mut rng), "None");
+    }
+}
@@ -0,0 +1,195 @@
+extern crate specs;
+use super::{
+    effects::{EffectQueue, EffectType, Targets},
+    gamelog::{GameLog, LogBuilder},
+    Confusion, EntityMoved, Map, Monster, Name, Point, Position, RangedAttack, RangedAttacker,
+    Renderable, RunState, TileType, Viewshed, WantsToPickupItem, WantsToUseItem,
+};
+use crate::color::*;
+use specs::prelude::*;
+extern crate rltk;
+
+/// Archers and spellcasters: keep the player at their preferred distance and attack
+/// whenever the player is in sight and the cooldown has run out. Runs before `MonsterAI`,
+/// which takes over when they have nowhere left to back off to, or need to close in.
+pub struct RangedMonsterAI {}
+
+impl<'a> System<'a> for RangedMonsterAI {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteExpect<'a, Map>,
+        ReadExpect<'a, Point>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, RunState>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, EffectQueue>,
+        Entities<'a>,
+        WriteStorage<'a, Viewshed>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, Name>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, RangedAttacker>,
+        ReadStorage<'a, Confusion>,
+        WriteStorage<'a, EntityMoved>,
+        ReadStorage<'a, WantsToUseItem>,
+        ReadStorage<'a, WantsToPickupItem>,
+        ReadStorage<'a, Renderable>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            mut map,
+            player_pos,
+            player_entity,
+            runstate,
+            mut log,
+            mut effects,
+            entities,
+            mut viewsheds,
+            monsters,
+            names,
+            mut positions,
+            mut ranged_attackers,
+            confused,
+            mut entity_moved,
+            wants_use,
+            wants_pickup,
+            renderables,
+        ) = data;
+
+        // only run system if the state is MonsterTurn
+        if *runstate != RunState::MonsterTurn {
+            return;
+        }
+
+        let player_confused = confused.get(*player_entity).is_some();
+
+        for (entity, viewshed, _monster, name, pos, attacker) in (
+            &entities,
+            &mut viewsheds,
+            &monsters,
+            &names,
+            &mut positions,
+            &mut ranged_attackers,
+        )
+            .join()
+        {
+            if attacker.turns_until_ready > 0 {
+                attacker.turns_until_ready -= 1;
+            }
+
+            let busy = wants_use.get(entity).is_some()
+                || wants_pickup.get(entity).is_some()
+                || entity_moved.get(entity).is_some();
+            if busy
+                || confused.get(entity).is_some()
+                || !viewshed.visible_tiles.contains(&*player_pos)
+            {
+                continue;
+            }
+
+            let here = Point::new(pos.x, pos.y);
+            let distance = rltk::DistanceAlg::Pythagoras.distance2d(here, *player_pos);
+
+            // Too close for comfort, back off if there's room
+            if distance < attacker.preferred_distance as f32 {
+                if let Some(retreat) = retreat_tile(&map, here, *player_pos) {
+                    let idx = map.xy_idx(pos.x, pos.y);
+                    map.blocked_tiles[idx] = false;
+                    pos.x = retreat.x;
+                    pos.y = retreat.y;
+                    entity_moved
+                        .insert(entity, EntityMoved {})
+                        .expect("Unable to insert marker");
+                    let new_idx = map.xy_idx(pos.x, pos.y);
+                    map.blocked_tiles[new_idx] = true;
+                    viewshed.dirty = true;
+                    continue;
+                }
+            }
+
+            // Cornered ones fight in melee like everybody else
+            if distance < 1.5 || distance > attacker.range as f32 || attacker.turns_until_ready > 0
+            {
+                continue;
+            }
+            // No point confusing someone who already is
+            let attack = attacker.attacks.iter().find(|attack| {
+                !(player_confused && matches!(attack, RangedAttack::Confusion { .. }))
+            });
+            let attack = match attack {
+                None => continue,
+                Some(attack) => *attack,
+            };
+
+            let target = Targets::Single {
+                target: *player_entity,
+            };
+            let (message, effect, glyph, fg) = match attack {
+                RangedAttack::Arrow { damage } => (
+                    " shoots an arrow at you!",
+                    EffectType::Damage { amount: damage },
+                    rltk::to_cp437('/'),
+                    brown(),
+                ),
+                RangedAttack::MagicMissile { damage } => (
+                    " casts magic missile at you!",
+                    EffectType::Damage { amount: damage },
+                    rltk::to_cp437('*'),
+                    cyan(),
+                ),
+                RangedAttack::Confusion { turns } => (
+                    " casts confusion at you!",
+                    EffectType::Confusion { turns },
+                    rltk::to_cp437('?'),
+                    magenta(),
+                ),
+            };
+            LogBuilder::new()
+                .append("The ")
+                .name(
+                    &name.name,
+                    renderables.get(entity).map_or(white(), |render| render.fg),
+                )
+                .append(message)
+                .log(&mut log);
+            effects.add_effect(
+                Some(entity),
+                EffectType::Particle {
+                    glyph,
+                    fg,
+                    bg: black(),
+                    lifespan: 200.0,
+                },
+                target.clone(),
+            );
+            effects.add_effect(Some(entity), effect, target);
+            attacker.turns_until_ready = attacker.cooldown;
+        }
+    }
+}
+
+/// The walkable tile next to `here` that puts the most distance between it and the player,
+/// as long as that's further away than standing still.
+fn retreat_tile(map: &Map, here: Point, player_pos: Point) -> Option<Point> {
+    let distance = |p: Point| rltk::DistanceAlg::Pythagoras.distance2d(p, player_pos);
+    let mut best: Option<(Point, f32)> = None;
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let (x, y) = (here.x + dx, here.y + dy);
+            if x < 1 || y < 1 || x > map.width - 2 || y > map.height - 2 {
+                continue;
+            }
+            let idx = map.xy_idx(x, y);
+            if map.tiles[idx] == TileType::Wall || map.blocked_tiles[idx] {
+                continue;
+            }
+            let candidate = Point::new(x, y);
+            let candidate_distance = distance(candidate);
+            if candidate_distance > best.map_or(distance(here), |(_p, d)| d) {
+                best = Some((candidate, candidate_distance));
+            }
+        }
+    }
+    best.map(|(p, _d)| p)
+}
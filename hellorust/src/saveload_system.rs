@@ -2,6 +2,8 @@
 extern crate specs;
 use super::{Map, SerializeMe, MAP_COUNT};
 use crate::components::*;
+use crate::identification::MagicItemNames;
+use crate::statistics::RunStats;
 use specs::error::NoError;
 use specs::prelude::*;
 use specs::saveload::{
@@ -11,7 +13,7 @@ use std::fs::File;
 use std::path::Path;
 
 pub fn save_exists() -> bool {
-    return Path::new("savegame.json").exists();
+    Path::new("savegame.json").exists()
 }
 
 pub fn delete_save() {
@@ -65,9 +67,15 @@ pub fn save_game(_ecs: &mut World) {
 pub fn save_game(ecs: &mut World) {
     // Create helper
     let mapcopy = ecs.get_mut::<Map>().unwrap().clone();
+    let magic_item_names = ecs.get_mut::<MagicItemNames>().unwrap().clone();
+    let run_stats = ecs.get_mut::<RunStats>().unwrap().clone();
     let savehelper = ecs
         .create_entity()
-        .with(SerializationHelper { map: mapcopy })
+        .with(SerializationHelper {
+            map: mapcopy,
+            magic_item_names,
+            run_stats,
+        })
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
 
@@ -92,7 +100,6 @@ pub fn save_game(ecs: &mut World) {
             Name,
             BlocksTile,
             CombatStats,
-            SufferDamage,
             WantsToMelee,
             Item,
             Consumable,
@@ -119,7 +126,29 @@ pub fn save_game(ecs: &mut World) {
             Hidden,
             EntryTrigger,
             EntityMoved,
-            SingleActivation
+            SingleActivation,
+            RandomTeleport,
+            ObfuscatedName,
+            IdentifiesItem,
+            CursedItem,
+            ProvidesRemoveCurse,
+            Charges,
+            Wallet,
+            GoldPile,
+            Vendor,
+            Bystander,
+            ManaPool,
+            KnownSpell,
+            TeachesSpell,
+            Companion,
+            SummonsCompanion,
+            Corpse,
+            LootTable,
+            RangedAttacker,
+            Boss,
+            Artifact,
+            Character,
+            Experience
         );
     }
 
@@ -167,7 +196,6 @@ pub fn load_game(ecs: &mut World) {
             Name,
             BlocksTile,
             CombatStats,
-            SufferDamage,
             WantsToMelee,
             Item,
             Consumable,
@@ -194,7 +222,29 @@ pub fn load_game(ecs: &mut World) {
             Hidden,
             EntryTrigger,
             EntityMoved,
-            SingleActivation
+            SingleActivation,
+            RandomTeleport,
+            ObfuscatedName,
+            IdentifiesItem,
+            CursedItem,
+            ProvidesRemoveCurse,
+            Charges,
+            Wallet,
+            GoldPile,
+            Vendor,
+            Bystander,
+            ManaPool,
+            KnownSpell,
+            TeachesSpell,
+            Companion,
+            SummonsCompanion,
+            Corpse,
+            LootTable,
+            RangedAttacker,
+            Boss,
+            Artifact,
+            Character,
+            Experience
         );
     }
 
@@ -214,6 +264,10 @@ pub fn load_game(ecs: &mut World) {
             let mut worldmap = ecs.write_resource::<Map>();
             *worldmap = h.map.clone();
             worldmap.tile_content = vec![Vec::new(); MAP_COUNT];
+            let mut magic_item_names = ecs.write_resource::<MagicItemNames>();
+            *magic_item_names = h.magic_item_names.clone();
+            let mut run_stats = ecs.write_resource::<RunStats>();
+            *run_stats = h.run_stats.clone();
             deleteme = Some(e);
         }
         // Then we find the player, by iterating entities with a
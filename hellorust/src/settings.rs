@@ -0,0 +1,103 @@
+use super::gamelog::{GameLog, DEFAULT_LOG_CAPACITY};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use std::fs::File;
+
+const SETTINGS_FILE: &str = "./settings.json";
+
+pub const MAPGEN_SPEEDS_MS: [f32; 6] = [50.0, 100.0, 200.0, 300.0, 500.0, 1000.0];
+pub const TOOLTIP_DELAYS_MS: [f32; 5] = [0.0, 250.0, 500.0, 1000.0, 2000.0];
+pub const LOG_LENGTHS: [usize; 5] = [100, 250, 500, 1000, 2000];
+
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize, Debug)]
+pub enum Font {
+    Terminal8x8,
+    Vga8x16,
+}
+
+/// Everything on the options screen. Kept in settings.json between runs.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+    pub fullscreen: bool,
+    pub font: Font,
+    pub show_mapgen_visualizer: bool,
+    /// How long each step of the map generation visualizer stays on screen.
+    pub mapgen_step_ms: f32,
+    /// How long the mouse has to rest on something before its tooltip shows up.
+    pub tooltip_delay_ms: f32,
+    /// How many messages the game log holds on to.
+    pub log_length: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            fullscreen: true,
+            font: Font::Terminal8x8,
+            show_mapgen_visualizer: true,
+            mapgen_step_ms: 300.0,
+            tooltip_delay_ms: 0.0,
+            log_length: DEFAULT_LOG_CAPACITY,
+        }
+    }
+}
+
+/// Reads settings.json. A missing or unreadable file means the defaults.
+pub fn load_settings() -> Settings {
+    std::fs::read_to_string(SETTINGS_FILE)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_settings(settings: &Settings) {
+    if let Ok(writer) = File::create(SETTINGS_FILE) {
+        serde_json::to_writer_pretty(writer, settings).expect("Unable to write settings");
+    }
+}
+
+/// Puts the settings that can change mid-game into effect. Fullscreen and the font
+/// are only read when the window opens.
+pub fn apply_settings(ecs: &World) {
+    let log_length = ecs.fetch::<Settings>().log_length;
+    ecs.fetch_mut::<GameLog>().set_capacity(log_length);
+}
+
+/// The next value in `choices` after `current`, wrapping around; `step` is 1 or -1.
+pub fn cycle<T: PartialEq + Copy>(choices: &[T], current: T, step: i32) -> T {
+    let count = choices.len() as i32;
+    let index = choices
+        .iter()
+        .position(|choice| *choice == current)
+        .map_or(0, |i| i as i32);
+    choices[(index + step).rem_euclid(count) as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycle_steps_forwards_and_backwards() {
+        assert_eq!(cycle(&LOG_LENGTHS, 250, 1), 500);
+        assert_eq!(cycle(&LOG_LENGTHS, 250, -1), 100);
+    }
+
+    #[test]
+    fn cycle_wraps_around_both_ends() {
+        assert_eq!(cycle(&LOG_LENGTHS, 2000, 1), 100);
+        assert_eq!(cycle(&LOG_LENGTHS, 100, -1), 2000);
+        assert_eq!(
+            cycle(&[Font::Terminal8x8, Font::Vga8x16], Font::Vga8x16, 1),
+            Font::Terminal8x8
+        );
+    }
+
+    #[test]
+    fn a_value_that_isnt_a_choice_is_treated_as_the_first() {
+        // e.g. a hand-edited settings.json
+        assert_eq!(cycle(&MAPGEN_SPEEDS_MS, 123.0, 1), 100.0);
+        assert_eq!(cycle(&MAPGEN_SPEEDS_MS, 123.0, -1), 1000.0);
+    }
+}
@@ -1,13 +1,18 @@
 extern crate rltk;
 extern crate specs;
 use super::color::*;
+use super::identification::MagicItemNames;
+use super::mana_system::MANA_REGEN_TURNS;
 use super::map::MAP_WIDTH;
 use super::{
-    AreaOfEffect, BlocksTile, CombatStats, Confusion, Consumable, DefenseBonus, Destructable,
-    EntryTrigger, EquipmentSlot, Equippable, Hidden, HungerClock, HungerState::*, InflictsDamage,
-    Item, MagicMapper, MeleePowerBonus, Monster, Name, Player, Position, ProvidesFood,
-    ProvidesHealing, RandomNumberGenerator, RandomTable, Ranged, Rect, Renderable, SerializeMe,
-    SingleActivation, Viewshed,
+    AreaOfEffect, Artifact, BlocksTile, Boss, Bystander, Charges, CombatStats, Companion,
+    Confusion, Consumable, Corpse, CursedItem, DefenseBonus, Destructable, EntryTrigger,
+    EquipmentSlot, Equippable, Experience, GoldPile, Hidden, HungerClock, HungerState::*,
+    IdentifiesItem, InBackpack, InflictsDamage, Item, KnownSpell, LootTable, MagicMapper, ManaPool,
+    MeleePowerBonus, Monster, Name, ObfuscatedName, Player, Position, ProvidesFood,
+    ProvidesHealing, ProvidesRemoveCurse, RandomNumberGenerator, RandomTable, RandomTeleport,
+    Ranged, RangedAttack, RangedAttacker, Rect, Renderable, SerializeMe, SingleActivation,
+    SummonsCompanion, TeachesSpell, Vendor, VendorCategory, Viewshed, Wallet,
 };
 use specs::prelude::*;
 use specs::saveload::{MarkedBuilder, SimpleMarker};
@@ -17,7 +22,8 @@ const MAX_MONSTERS: i32 = 4;
 
 /// Spawns the player and returns its entity object.
 pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
-    ecs.create_entity()
+    let player = ecs
+        .create_entity()
         .with(Position {
             x: player_x,
             y: player_y,
@@ -47,8 +53,96 @@ pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
             state: WellFed,
             duration: 20,
         })
+        .with(Wallet { gold: 50 })
+        .with(ManaPool {
+            mana: 10,
+            max_mana: 10,
+            regen_timer: MANA_REGEN_TURNS,
+        })
+        .with(Experience { level: 1, xp: 0 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build()
+        .build();
+
+    // Every adventurer has a loyal dog. It gets put next to the player once there's a map.
+    companion(ecs, player_x, player_y, rltk::to_cp437('d'), "Dog");
+    player
+}
+
+/// Puts a freshly spawned `name` straight into `owner`'s backpack.
+pub fn give_item(ecs: &mut World, owner: Entity, name: &str) -> Option<Entity> {
+    // Items spawn on the floor, so spawn it anywhere and pick it right back up
+    let item = spawn_entity(ecs, 0, 0, name)?;
+    ecs.write_storage::<Position>().remove(item);
+    ecs.write_storage::<InBackpack>()
+        .insert(item, InBackpack { owner })
+        .expect("Unable to insert backpack entry");
+    Some(item)
+}
+
+/// Gives `owner` the spell `name`. A spell does what the scroll of the same name does.
+pub fn learn_spell(ecs: &mut World, owner: Entity, name: &str) {
+    let spell = ecs.create_entity().with(Name {
+        name: name.to_string(),
+    });
+    let spell = match name {
+        "Magic Missile" => spell
+            .with(KnownSpell {
+                owner,
+                mana_cost: 2,
+            })
+            .with(Ranged { range: 6 })
+            .with(InflictsDamage { damage: 8 }),
+        "Fireball" => spell
+            .with(KnownSpell {
+                owner,
+                mana_cost: 5,
+            })
+            .with(Ranged { range: 6 })
+            .with(InflictsDamage { damage: 20 })
+            .with(AreaOfEffect { radius: 3 }),
+        "Confusion" => spell
+            .with(KnownSpell {
+                owner,
+                mana_cost: 3,
+            })
+            .with(Ranged { range: 6 })
+            .with(Confusion { turns: 4 }),
+        "Mending" => spell
+            .with(KnownSpell {
+                owner,
+                mana_cost: 4,
+            })
+            .with(ProvidesHealing { heal_amount: 8 }),
+        // Dropping the builder without building it deletes the half-made entity
+        _ => return,
+    };
+    spell.marked::<SimpleMarker<SerializeMe>>().build();
+}
+
+pub fn knows_spell(ecs: &World, owner: Entity, name: &str) -> bool {
+    let names = ecs.read_storage::<Name>();
+    let known_spells = ecs.read_storage::<KnownSpell>();
+    (&known_spells, &names)
+        .join()
+        .any(|(known, spell_name)| known.owner == owner && spell_name.name == name)
+}
+
+/// Unidentified scrolls go by a per-run gibberish title.
+fn scroll_name(ecs: &mut World, real_name: &str) -> ObfuscatedName {
+    let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+    let mut magic_item_names = ecs.write_resource::<MagicItemNames>();
+    ObfuscatedName {
+        name: magic_item_names.scroll_name(real_name, &mut rng),
+    }
+}
+
+/// Unidentified potions are only known by their looks.
+fn potion_name(ecs: &mut World, real_name: &str) -> ObfuscatedName {
+    let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+    let mut magic_item_names = ecs.write_resource::<MagicItemNames>();
+    ObfuscatedName {
+        name: magic_item_names.potion_name(real_name, &mut rng),
+    }
 }
 
 fn room_table(map_depth: i32) -> RandomTable {
@@ -59,29 +153,150 @@ fn room_table(map_depth: i32) -> RandomTable {
         .add("Health Potion", 7)
         .add("Magic Missile Scroll", 4)
         .add("Orc", 1 + map_depth)
+        .add("Goblin Archer", map_depth)
+        .add("Goblin Shaman", map_depth - 1)
         .add("Dagger", 3)
         .add("Shield", 3)
         .add("Dagger", 3)
         .add("Shield", 3)
         .add("Longsword", map_depth - 1)
         .add("Tower Shield", map_depth - 1)
+        .add("Greatsword", map_depth - 2)
+        .add("Leather Cap", 2)
+        .add("Leather Armor", 2)
+        .add("Leather Leggings", 2)
+        .add("Leather Boots", 2)
+        .add("Leather Gloves", 2)
+        .add("Amulet of Protection", map_depth - 2)
+        .add("Ring of Power", map_depth - 2)
+        .add("Ring of Protection", map_depth - 2)
         .add("Waffle", 10)
         .add("Magic Mapping Scroll", 3)
+        .add("Identify Scroll", 3)
+        .add("Remove Curse Scroll", map_depth - 1)
+        .add("Cursed Longsword", map_depth - 1)
+        .add("Cursed Tower Shield", map_depth - 1)
+        .add("Wand of Magic Missile", 2)
+        .add("Wand of Fireball", map_depth - 2)
+        .add("Wand of Confusion", map_depth - 1)
+        .add("Gold", 6)
+        .add("Vendor", 1)
+        .add("Tome of Fireball", map_depth - 2)
+        .add("Tome of Confusion", map_depth - 1)
+        .add("Tome of Mending", 1)
+        .add("Summoning Scroll", map_depth - 1)
         .add("Hidden Spike", 2)
+        .add("Teleport Trap", map_depth - 1)
+}
+
+pub fn orc(ecs: &mut World, x: i32, y: i32) -> Entity {
+    monster(ecs, x, y, rltk::to_cp437('o'), "Orc", "Orcs")
+}
+pub fn goblin(ecs: &mut World, x: i32, y: i32) -> Entity {
+    monster(ecs, x, y, rltk::to_cp437('g'), "Goblin", "Goblins")
+}
+pub fn goblin_archer(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let archer = monster(ecs, x, y, rltk::to_cp437('a'), "Goblin Archer", "Goblins");
+    ranged_attacker(
+        ecs,
+        archer,
+        vec![RangedAttack::Arrow { damage: 3 }],
+        6,
+        3,
+        1,
+    );
+    archer
+}
+/// Opens with confusion, then keeps the player busy with magic missiles.
+pub fn goblin_shaman(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let shaman = monster(ecs, x, y, rltk::to_cp437('s'), "Goblin Shaman", "Shamans");
+    ranged_attacker(
+        ecs,
+        shaman,
+        vec![
+            RangedAttack::Confusion { turns: 3 },
+            RangedAttack::MagicMissile { damage: 5 },
+        ],
+        6,
+        4,
+        3,
+    );
+    shaman
 }
 
-pub fn orc(ecs: &mut World, x: i32, y: i32) {
-    monster(ecs, x, y, rltk::to_cp437('o'), "Orc");
+/// Who waits at the bottom of each milestone depth, in order: name, glyph and the
+/// monster it calls for help. Past the last one they come around again, only tougher.
+const BOSSES: [(&str, char, &str); 2] = [
+    ("Goblin Warlord", 'G', "Goblin"),
+    ("Orc Chieftain", 'O', "Orc"),
+];
+
+/// Spawns the boss for the arena on `depth`. Its loot table is named after it, and
+/// always has something in it.
+pub fn boss(ecs: &mut World, x: i32, y: i32, depth: i32) {
+    let (name, glyph, minion) = BOSSES[((depth / 5 - 1) as usize) % BOSSES.len()];
+    let boss = monster(ecs, x, y, rltk::to_cp437(glyph), name, name);
+    ecs.write_storage::<Boss>()
+        .insert(
+            boss,
+            Boss {
+                minion: minion.to_string(),
+                phase: 1,
+            },
+        )
+        .expect("Unable to insert boss");
+    if let Some(stats) = ecs.write_storage::<CombatStats>().get_mut(boss) {
+        stats.max_hp = 40 + depth * 4;
+        stats.hp = stats.max_hp;
+        stats.defense = 2 + depth / 5;
+        stats.strength = 6 + depth / 5;
+    }
+    if let Some(renderable) = ecs.write_storage::<Renderable>().get_mut(boss) {
+        renderable.fg = magenta();
+    }
 }
-pub fn goblin(ecs: &mut World, x: i32, y: i32) {
-    monster(ecs, x, y, rltk::to_cp437('g'), "Goblin");
+
+/// Turns a freshly spawned monster into one that keeps its distance and attacks from range.
+/// They're frail up close to make up for it.
+fn ranged_attacker(
+    ecs: &mut World,
+    monster: Entity,
+    attacks: Vec<RangedAttack>,
+    range: i32,
+    preferred_distance: i32,
+    cooldown: i32,
+) {
+    ecs.write_storage::<RangedAttacker>()
+        .insert(
+            monster,
+            RangedAttacker {
+                attacks,
+                range,
+                preferred_distance,
+                cooldown,
+                turns_until_ready: 0,
+            },
+        )
+        .expect("Unable to insert ranged attacker");
+    if let Some(stats) = ecs.write_storage::<CombatStats>().get_mut(monster) {
+        stats.max_hp = 10;
+        stats.hp = 10;
+        stats.defense = 0;
+    }
 }
 
-pub fn monster<S: ToString>(ecs: &mut World, x: i32, y: i32, glyph: u16, name: S) {
+pub fn monster<S: ToString>(
+    ecs: &mut World,
+    x: i32,
+    y: i32,
+    glyph: u16,
+    name: S,
+    loot_table: &str,
+) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
-            glyph: glyph,
+            glyph,
             fg: red(),
             bg: black(),
             render_order: 1,
@@ -102,6 +317,39 @@ pub fn monster<S: ToString>(ecs: &mut World, x: i32, y: i32, glyph: u16, name: S
             defense: 1,
             strength: 4,
         })
+        .with(LootTable {
+            table: loot_table.to_string(),
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+/// Spawns a creature that fights alongside the player.
+pub fn companion<S: ToString>(ecs: &mut World, x: i32, y: i32, glyph: u16, name: S) {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph,
+            fg: green(),
+            bg: black(),
+            render_order: 1,
+        })
+        .with(Viewshed {
+            visible_tiles: Vec::new(),
+            range: 8,
+            dirty: true,
+        })
+        .with(Companion {})
+        .with(Name {
+            name: name.to_string(),
+        })
+        .with(BlocksTile {})
+        .with(CombatStats {
+            max_hp: 12,
+            hp: 12,
+            defense: 1,
+            strength: 3,
+        })
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
 }
@@ -126,7 +374,7 @@ pub fn spawn_room(ecs: &mut World, room: &Rect, map_depth: i32) {
                 let idx = (y * MAP_WIDTH) + x;
                 // 1/2 don't let entities spawn in same spot
                 // 2/2 don't let entities spawn in center of room (hacky fix for items hiding ladder)
-                if !spawn_points.contains_key(&idx) && !((x as i32, y as i32) == room.center()) {
+                if !spawn_points.contains_key(&idx) && ((x as i32, y as i32) != room.center()) {
                     spawn_points.insert(idx, spawn_table.roll(&mut rng));
                     added = true;
                 } else {
@@ -140,26 +388,87 @@ pub fn spawn_room(ecs: &mut World, room: &Rect, map_depth: i32) {
         let x = (*spawn.0 % MAP_WIDTH) as i32;
         let y = (*spawn.0 / MAP_WIDTH) as i32;
 
-        match spawn.1.as_ref() {
-            "Confusion Scroll" => confusion_scroll(ecs, x, y),
-            "Fireball Scroll" => fireball_scroll(ecs, x, y),
-            "Goblin" => goblin(ecs, x, y),
-            "Health Potion" => health_potion(ecs, x, y),
-            "Magic Missile Scroll" => magic_missile_scroll(ecs, x, y),
-            "Orc" => orc(ecs, x, y),
-            "Dagger" => dagger(ecs, x, y),
-            "Shield" => shield(ecs, x, y),
-            "Longsword" => longsword(ecs, x, y),
-            "Tower Shield" => tower_shield(ecs, x, y),
-            "Waffle" => waffle(ecs, x, y),
-            "Magic Mapping Scroll" => magic_mapping_scroll(ecs, x, y),
-            "Hidden Spike" => hidden_spike(ecs, x, y),
-            _ => {}
-        }
+        spawn_entity(ecs, x, y, spawn.1);
     }
 }
 
-fn waffle(ecs: &mut World, x: i32, y: i32) {
+/// What monsters might have on them when they die. "None" drops nothing.
+pub fn loot_table(table: &str) -> RandomTable {
+    match table {
+        "Goblins" => RandomTable::new()
+            .add("None", 8)
+            .add("Gold", 6)
+            .add("Dagger", 2)
+            .add("Health Potion", 2)
+            .add("Waffle", 2),
+        "Orcs" => RandomTable::new()
+            .add("None", 8)
+            .add("Gold", 6)
+            .add("Health Potion", 2)
+            .add("Shield", 2)
+            .add("Leather Armor", 1)
+            .add("Longsword", 1),
+        "Goblin Warlord" => RandomTable::new().add("Warlord's Cleaver", 1),
+        "Orc Chieftain" => RandomTable::new().add("Chieftain's Plate", 1),
+        "Shamans" => RandomTable::new()
+            .add("None", 6)
+            .add("Magic Missile Scroll", 3)
+            .add("Confusion Scroll", 2)
+            .add("Wand of Magic Missile", 1),
+        _ => RandomTable::new(),
+    }
+}
+
+/// Spawns whatever the spawn tables call `name` at (x, y). Unknown names spawn nothing.
+pub fn spawn_entity(ecs: &mut World, x: i32, y: i32, name: &str) -> Option<Entity> {
+    let entity = match name {
+        "Confusion Scroll" => confusion_scroll(ecs, x, y),
+        "Fireball Scroll" => fireball_scroll(ecs, x, y),
+        "Goblin" => goblin(ecs, x, y),
+        "Goblin Archer" => goblin_archer(ecs, x, y),
+        "Goblin Shaman" => goblin_shaman(ecs, x, y),
+        "Health Potion" => health_potion(ecs, x, y),
+        "Magic Missile Scroll" => magic_missile_scroll(ecs, x, y),
+        "Orc" => orc(ecs, x, y),
+        "Dagger" => dagger(ecs, x, y),
+        "Shield" => shield(ecs, x, y),
+        "Longsword" => longsword(ecs, x, y),
+        "Tower Shield" => tower_shield(ecs, x, y),
+        "Greatsword" => greatsword(ecs, x, y),
+        "Warlord's Cleaver" => warlords_cleaver(ecs, x, y),
+        "Chieftain's Plate" => chieftains_plate(ecs, x, y),
+        "Heart of the Mountain" => heart_of_the_mountain(ecs, x, y),
+        "Leather Cap" => leather_cap(ecs, x, y),
+        "Leather Armor" => leather_armor(ecs, x, y),
+        "Leather Leggings" => leather_leggings(ecs, x, y),
+        "Leather Boots" => leather_boots(ecs, x, y),
+        "Leather Gloves" => leather_gloves(ecs, x, y),
+        "Amulet of Protection" => amulet_of_protection(ecs, x, y),
+        "Ring of Power" => ring_of_power(ecs, x, y),
+        "Ring of Protection" => ring_of_protection(ecs, x, y),
+        "Waffle" => waffle(ecs, x, y),
+        "Magic Mapping Scroll" => magic_mapping_scroll(ecs, x, y),
+        "Identify Scroll" => identify_scroll(ecs, x, y),
+        "Remove Curse Scroll" => remove_curse_scroll(ecs, x, y),
+        "Cursed Longsword" => cursed_longsword(ecs, x, y),
+        "Cursed Tower Shield" => cursed_tower_shield(ecs, x, y),
+        "Wand of Magic Missile" => magic_missile_wand(ecs, x, y),
+        "Wand of Fireball" => fireball_wand(ecs, x, y),
+        "Wand of Confusion" => confusion_wand(ecs, x, y),
+        "Tome of Fireball" => tome(ecs, x, y, "Fireball"),
+        "Tome of Confusion" => tome(ecs, x, y, "Confusion"),
+        "Tome of Mending" => tome(ecs, x, y, "Mending"),
+        "Summoning Scroll" => summoning_scroll(ecs, x, y),
+        "Gold" => gold_pile(ecs, x, y),
+        "Vendor" => random_vendor(ecs, x, y),
+        "Hidden Spike" => hidden_spike(ecs, x, y),
+        "Teleport Trap" => teleport_trap(ecs, x, y),
+        _ => return None,
+    };
+    Some(entity)
+}
+
+fn waffle(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -171,19 +480,23 @@ fn waffle(ecs: &mut World, x: i32, y: i32) {
         .with(Name {
             name: "Waffle".to_string(),
         })
-        .with(Item {})
-        .with(ProvidesFood {})
+        .with(Item {
+            weight: 1.0,
+            value: 5,
+        })
+        .with(ProvidesFood { nutrition: 20 })
         .with(Consumable {})
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-pub fn health_potion(ecs: &mut World, x: i32, y: i32) {
+pub fn health_potion(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let obfuscated_name = potion_name(ecs, "Health Potion");
     let glyph = rltk::to_cp437('¡');
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
-            glyph: glyph,
+            glyph,
             fg: magenta(),
             bg: black(),
             render_order: 2,
@@ -191,20 +504,25 @@ pub fn health_potion(ecs: &mut World, x: i32, y: i32) {
         .with(Name {
             name: "Health Potion".to_string(),
         })
-        .with(Item {})
+        .with(Item {
+            weight: 0.5,
+            value: 25,
+        })
+        .with(obfuscated_name)
         .with(Destructable { broken: false })
         .with(ProvidesHealing { heal_amount: 8 })
         .with(Consumable {})
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-pub fn magic_missile_scroll(ecs: &mut World, x: i32, y: i32) {
+pub fn magic_missile_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let obfuscated_name = scroll_name(ecs, "Magic Missile Scroll");
     let glyph = rltk::to_cp437(')');
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
-            glyph: glyph,
+            glyph,
             fg: cyan(),
             bg: black(),
             render_order: 2,
@@ -212,21 +530,26 @@ pub fn magic_missile_scroll(ecs: &mut World, x: i32, y: i32) {
         .with(Name {
             name: "Magic Missile Scroll".to_string(),
         })
-        .with(Item {})
+        .with(Item {
+            weight: 0.5,
+            value: 30,
+        })
+        .with(obfuscated_name)
         .with(Destructable { broken: false })
         .with(Ranged { range: 6 })
         .with(InflictsDamage { damage: 8 })
         .with(Consumable {})
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-pub fn fireball_scroll(ecs: &mut World, x: i32, y: i32) {
+pub fn fireball_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let obfuscated_name = scroll_name(ecs, "Fireball scroll");
     let glyph = rltk::to_cp437(')');
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
-            glyph: glyph,
+            glyph,
             fg: orange(),
             bg: black(),
             render_order: 2,
@@ -234,22 +557,27 @@ pub fn fireball_scroll(ecs: &mut World, x: i32, y: i32) {
         .with(Name {
             name: "Fireball scroll".to_string(),
         })
-        .with(Item {})
+        .with(Item {
+            weight: 0.5,
+            value: 50,
+        })
+        .with(obfuscated_name)
         .with(Destructable { broken: false })
         .with(Ranged { range: 6 })
         .with(InflictsDamage { damage: 20 })
         .with(AreaOfEffect { radius: 3 })
         .with(Consumable {})
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-pub fn confusion_scroll(ecs: &mut World, x: i32, y: i32) {
+pub fn confusion_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let obfuscated_name = scroll_name(ecs, "Confusion scroll");
     let glyph = rltk::to_cp437(')');
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
-            glyph: glyph,
+            glyph,
             fg: pink(),
             bg: black(),
             render_order: 2,
@@ -257,16 +585,179 @@ pub fn confusion_scroll(ecs: &mut World, x: i32, y: i32) {
         .with(Name {
             name: "Confusion scroll".to_string(),
         })
-        .with(Item {})
+        .with(Item {
+            weight: 0.5,
+            value: 30,
+        })
+        .with(obfuscated_name)
         .with(Destructable { broken: false })
         .with(Ranged { range: 6 })
         .with(Confusion { turns: 4 })
         .with(Consumable {})
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
+}
+
+fn summoning_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let obfuscated_name = scroll_name(ecs, "Scroll of Summoning");
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437(')'),
+            fg: green(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Scroll of Summoning".to_string(),
+        })
+        .with(Item {
+            weight: 0.5,
+            value: 60,
+        })
+        .with(obfuscated_name)
+        .with(Destructable { broken: false })
+        .with(SummonsCompanion {})
+        .with(Consumable {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn identify_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let obfuscated_name = scroll_name(ecs, "Scroll of Identify");
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437(')'),
+            fg: white(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Scroll of Identify".to_string(),
+        })
+        .with(Item {
+            weight: 0.5,
+            value: 20,
+        })
+        .with(obfuscated_name)
+        .with(Destructable { broken: false })
+        .with(IdentifiesItem {})
+        .with(Consumable {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
 }
 
-fn dagger(ecs: &mut World, x: i32, y: i32) {
+fn remove_curse_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let obfuscated_name = scroll_name(ecs, "Scroll of Remove Curse");
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437(')'),
+            fg: yellow(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Scroll of Remove Curse".to_string(),
+        })
+        .with(Item {
+            weight: 0.5,
+            value: 40,
+        })
+        .with(obfuscated_name)
+        .with(Destructable { broken: false })
+        .with(ProvidesRemoveCurse {})
+        .with(Consumable {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+/// Wands start out with a few charges. An empty wand stays in the pack, but zapping it
+/// makes it explode, which destroys it.
+fn wand_charges(ecs: &mut World, base: i32) -> Charges {
+    let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+    let charges = base + rng.roll_dice(1, 3);
+    Charges {
+        charges,
+        max_charges: charges,
+    }
+}
+
+fn magic_missile_wand(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let charges = wand_charges(ecs, 2);
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('-'),
+            fg: cyan(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Wand of Magic Missile".to_string(),
+        })
+        .with(Item {
+            weight: 1.0,
+            value: 100,
+        })
+        .with(Ranged { range: 6 })
+        .with(InflictsDamage { damage: 6 })
+        .with(charges)
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn fireball_wand(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let charges = wand_charges(ecs, 1);
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('-'),
+            fg: orange(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Wand of Fireball".to_string(),
+        })
+        .with(Item {
+            weight: 1.0,
+            value: 150,
+        })
+        .with(Ranged { range: 6 })
+        .with(InflictsDamage { damage: 12 })
+        .with(AreaOfEffect { radius: 2 })
+        .with(charges)
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn confusion_wand(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let charges = wand_charges(ecs, 2);
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('-'),
+            fg: pink(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Wand of Confusion".to_string(),
+        })
+        .with(Item {
+            weight: 1.0,
+            value: 100,
+        })
+        .with(Ranged { range: 6 })
+        .with(Confusion { turns: 4 })
+        .with(charges)
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn dagger(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -278,17 +769,21 @@ fn dagger(ecs: &mut World, x: i32, y: i32) {
         .with(Name {
             name: "Dagger".to_string(),
         })
-        .with(Item {})
+        .with(Item {
+            weight: 1.0,
+            value: 10,
+        })
         .with(Destructable { broken: false })
         .with(Equippable {
             slot: EquipmentSlot::Melee,
+            two_handed: false,
         })
         .with(MeleePowerBonus { power: 2 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn shield(ecs: &mut World, x: i32, y: i32) {
+fn shield(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -300,16 +795,21 @@ fn shield(ecs: &mut World, x: i32, y: i32) {
         .with(Name {
             name: "Shield".to_string(),
         })
-        .with(Item {})
+        .with(Item {
+            weight: 5.0,
+            value: 20,
+        })
+        .with(Destructable { broken: false })
         .with(Equippable {
             slot: EquipmentSlot::Shield,
+            two_handed: false,
         })
         .with(DefenseBonus { defense: 1 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn longsword(ecs: &mut World, x: i32, y: i32) {
+fn longsword(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -321,17 +821,21 @@ fn longsword(ecs: &mut World, x: i32, y: i32) {
         .with(Name {
             name: "Longsword".to_string(),
         })
-        .with(Item {})
+        .with(Item {
+            weight: 3.0,
+            value: 40,
+        })
         .with(Destructable { broken: false })
         .with(Equippable {
             slot: EquipmentSlot::Melee,
+            two_handed: false,
         })
         .with(MeleePowerBonus { power: 4 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn tower_shield(ecs: &mut World, x: i32, y: i32) {
+fn tower_shield(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -343,16 +847,300 @@ fn tower_shield(ecs: &mut World, x: i32, y: i32) {
         .with(Name {
             name: "Tower Shield".to_string(),
         })
-        .with(Item {})
+        .with(Item {
+            weight: 10.0,
+            value: 50,
+        })
+        .with(Destructable { broken: false })
         .with(Equippable {
             slot: EquipmentSlot::Shield,
+            two_handed: false,
         })
         .with(DefenseBonus { defense: 2 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
+}
+
+fn greatsword(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('/'),
+            fg: orange(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Greatsword".to_string(),
+        })
+        .with(Item {
+            weight: 8.0,
+            value: 60,
+        })
+        .with(Destructable { broken: false })
+        .with(Equippable {
+            slot: EquipmentSlot::Melee,
+            two_handed: true,
+        })
+        .with(MeleePowerBonus { power: 6 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+/// What the whole descent is for. Lies on the final depth, behind its boss.
+fn heart_of_the_mountain(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('*'),
+            fg: yellow(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Heart of the Mountain".to_string(),
+        })
+        .with(Item {
+            weight: 2.0,
+            value: 5000,
+        })
+        .with(Artifact {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+/// Boss loot: only ever dropped by the Goblin Warlord.
+fn warlords_cleaver(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('/'),
+            fg: magenta(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Warlord's Cleaver".to_string(),
+        })
+        .with(Item {
+            weight: 4.0,
+            value: 150,
+        })
+        .with(Destructable { broken: false })
+        .with(Equippable {
+            slot: EquipmentSlot::Melee,
+            two_handed: false,
+        })
+        .with(MeleePowerBonus { power: 7 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+/// Boss loot: only ever dropped by the Orc Chieftain.
+fn chieftains_plate(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('['),
+            fg: magenta(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Chieftain's Plate".to_string(),
+        })
+        .with(Item {
+            weight: 15.0,
+            value: 150,
+        })
+        .with(Equippable {
+            slot: EquipmentSlot::Torso,
+            two_handed: false,
+        })
+        .with(DefenseBonus { defense: 4 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn leather_cap(ecs: &mut World, x: i32, y: i32) -> Entity {
+    armor(ecs, x, y, "Leather Cap", EquipmentSlot::Head, 1.0, 10)
+}
+fn leather_armor(ecs: &mut World, x: i32, y: i32) -> Entity {
+    armor(ecs, x, y, "Leather Armor", EquipmentSlot::Torso, 8.0, 30)
+}
+fn leather_leggings(ecs: &mut World, x: i32, y: i32) -> Entity {
+    armor(ecs, x, y, "Leather Leggings", EquipmentSlot::Legs, 4.0, 15)
+}
+fn leather_boots(ecs: &mut World, x: i32, y: i32) -> Entity {
+    armor(ecs, x, y, "Leather Boots", EquipmentSlot::Feet, 2.0, 10)
+}
+fn leather_gloves(ecs: &mut World, x: i32, y: i32) -> Entity {
+    armor(ecs, x, y, "Leather Gloves", EquipmentSlot::Hands, 1.0, 10)
+}
+
+/// Every piece of leather armor is worth one point of defense.
+fn armor<S: ToString>(
+    ecs: &mut World,
+    x: i32,
+    y: i32,
+    name: S,
+    slot: EquipmentSlot,
+    weight: f32,
+    value: i32,
+) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('['),
+            fg: brown(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: name.to_string(),
+        })
+        .with(Item { weight, value })
+        .with(Equippable {
+            slot,
+            two_handed: false,
+        })
+        .with(DefenseBonus { defense: 1 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn amulet_of_protection(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('"'),
+            fg: cyan(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Amulet of Protection".to_string(),
+        })
+        .with(Item {
+            weight: 0.1,
+            value: 100,
+        })
+        .with(Equippable {
+            slot: EquipmentSlot::Amulet,
+            two_handed: false,
+        })
+        .with(DefenseBonus { defense: 2 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn ring_of_power(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('='),
+            fg: yellow(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Ring of Power".to_string(),
+        })
+        .with(Item {
+            weight: 0.1,
+            value: 100,
+        })
+        .with(Equippable {
+            slot: EquipmentSlot::Ring,
+            two_handed: false,
+        })
+        .with(MeleePowerBonus { power: 1 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn ring_of_protection(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('='),
+            fg: cyan(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Ring of Protection".to_string(),
+        })
+        .with(Item {
+            weight: 0.1,
+            value: 100,
+        })
+        .with(Equippable {
+            slot: EquipmentSlot::Ring,
+            two_handed: false,
+        })
+        .with(DefenseBonus { defense: 1 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
 }
 
-fn magic_mapping_scroll(ecs: &mut World, x: i32, y: i32) {
+// Cursed equipment looks just like the real thing until you put it on.
+fn cursed_longsword(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('/'),
+            fg: yellow(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Longsword".to_string(),
+        })
+        .with(Item {
+            weight: 3.0,
+            value: 40,
+        })
+        .with(Destructable { broken: false })
+        .with(Equippable {
+            slot: EquipmentSlot::Melee,
+            two_handed: false,
+        })
+        .with(MeleePowerBonus { power: -2 })
+        .with(CursedItem {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn cursed_tower_shield(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('('),
+            fg: yellow(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Tower Shield".to_string(),
+        })
+        .with(Item {
+            weight: 10.0,
+            value: 50,
+        })
+        .with(Destructable { broken: false })
+        .with(Equippable {
+            slot: EquipmentSlot::Shield,
+            two_handed: false,
+        })
+        .with(DefenseBonus { defense: -2 })
+        .with(CursedItem {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn magic_mapping_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let obfuscated_name = scroll_name(ecs, "Scroll of Magic Mapping");
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -364,13 +1152,17 @@ fn magic_mapping_scroll(ecs: &mut World, x: i32, y: i32) {
         .with(Name {
             name: "Scroll of Magic Mapping".to_string(),
         })
-        .with(Item {})
+        .with(Item {
+            weight: 0.5,
+            value: 50,
+        })
+        .with(obfuscated_name)
         .with(MagicMapper {})
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn hidden_spike(ecs: &mut World, x: i32, y: i32) {
+fn hidden_spike(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -387,5 +1179,201 @@ fn hidden_spike(ecs: &mut World, x: i32, y: i32) {
         .with(InflictsDamage { damage: 6 })
         .with(SingleActivation {})
         .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn teleport_trap(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('^'),
+            fg: magenta(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Teleport Trap".to_string(),
+        })
+        .with(Hidden {})
+        .with(EntryTrigger {})
+        .with(RandomTeleport {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+/// Reading a tome teaches you a spell for good.
+fn tome(ecs: &mut World, x: i32, y: i32, spell: &str) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('≡'),
+            fg: magenta(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: format!("Tome of {}", spell),
+        })
+        .with(Item {
+            weight: 2.0,
+            value: 80,
+        })
+        .with(Consumable {})
+        .with(TeachesSpell {
+            spell: spell.to_string(),
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn gold_pile(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let amount = ecs
+        .write_resource::<RandomNumberGenerator>()
+        .roll_dice(3, 6);
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('$'),
+            fg: yellow(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Gold".to_string(),
+        })
+        .with(Item {
+            weight: 0.0,
+            value: amount,
+        })
+        .with(GoldPile { amount })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn random_vendor(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let roll = ecs
+        .write_resource::<RandomNumberGenerator>()
+        .roll_dice(1, 3);
+    match roll {
+        1 => vendor(ecs, x, y, VendorCategory::General),
+        2 => vendor(ecs, x, y, VendorCategory::Weaponsmith),
+        _ => vendor(ecs, x, y, VendorCategory::Alchemist),
+    }
+}
+
+fn vendor_table(category: VendorCategory) -> RandomTable {
+    match category {
+        VendorCategory::General => RandomTable::new()
+            .add("Waffle", 5)
+            .add("Health Potion", 3)
+            .add("Dagger", 2)
+            .add("Shield", 2)
+            .add("Leather Cap", 1)
+            .add("Leather Boots", 1)
+            .add("Leather Gloves", 1),
+        VendorCategory::Weaponsmith => RandomTable::new()
+            .add("Dagger", 3)
+            .add("Longsword", 2)
+            .add("Greatsword", 1)
+            .add("Shield", 3)
+            .add("Tower Shield", 1)
+            .add("Leather Armor", 2)
+            .add("Leather Leggings", 2),
+        VendorCategory::Alchemist => RandomTable::new()
+            .add("Health Potion", 5)
+            .add("Magic Missile Scroll", 3)
+            .add("Fireball Scroll", 2)
+            .add("Confusion Scroll", 2)
+            .add("Identify Scroll", 3)
+            .add("Remove Curse Scroll", 2)
+            .add("Tome of Mending", 1)
+            .add("Tome of Fireball", 1)
+            .add("Wand of Magic Missile", 1),
+    }
+}
+
+/// Spawns a peaceful trader with a few things from its category's table for sale.
+pub fn vendor(ecs: &mut World, x: i32, y: i32, category: VendorCategory) -> Entity {
+    let name = match category {
+        VendorCategory::General => "Shopkeeper",
+        VendorCategory::Weaponsmith => "Weaponsmith",
+        VendorCategory::Alchemist => "Alchemist",
+    };
+    let vendor = ecs
+        .create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('@'),
+            fg: green(),
+            bg: black(),
+            render_order: 1,
+        })
+        .with(Name {
+            name: name.to_string(),
+        })
+        .with(BlocksTile {})
+        .with(Vendor { category })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+
+    let stock: Vec<String> = {
+        let table = vendor_table(category);
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        let count = rng.roll_dice(1, 4) + 4;
+        (0..count).map(|_| table.roll(&mut rng)).collect()
+    };
+    for item in stock.iter() {
+        give_item(ecs, vendor, item);
+    }
+    vendor
+}
+
+/// Spawns a peaceful townsperson: it wanders around and can't be attacked.
+pub fn bystander<S: ToString>(ecs: &mut World, x: i32, y: i32, name: S) {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('@'),
+            fg: white(),
+            bg: black(),
+            render_order: 1,
+        })
+        .with(Name {
+            name: name.to_string(),
+        })
+        .with(BlocksTile {})
+        .with(Bystander {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+}
+
+/// What a slain monster leaves behind. Some monsters are better eating than others.
+pub fn corpse(ecs: &mut World, x: i32, y: i32, monster_name: &str) {
+    let (nutrition, poison_chance) = match monster_name {
+        "Orc" => (15, 25),
+        _ => (10, 10),
+    };
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('%'),
+            fg: red(),
+            bg: black(),
+            render_order: 2,
+        })
+        .with(Name {
+            name: format!("{} corpse", monster_name),
+        })
+        .with(Item {
+            weight: 20.0,
+            value: 0,
+        })
+        .with(ProvidesFood { nutrition })
+        .with(Consumable {})
+        .with(Corpse {
+            age: 0,
+            poison_chance,
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
         .build();
 }
@@ -0,0 +1,201 @@
+use super::{player::artifact_recovered, trade, HungerClock, HungerState, Name};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use std::collections::BTreeMap;
+use std::fs::File;
+
+const HIGH_SCORES_FILE: &str = "./highscores.json";
+const HIGH_SCORES_KEPT: usize = 10;
+const VICTORY_BONUS: i32 = 5000;
+
+/// Everything worth remembering about the current run. Lives in the save game.
+#[derive(Default, Serialize, Deserialize, Clone)]
+pub struct RunStats {
+    pub turns: i32,
+    /// Monster name to how many of them died.
+    pub kills: BTreeMap<String, i32>,
+    pub damage_dealt: i32,
+    pub damage_taken: i32,
+    pub items_used: i32,
+    pub deepest_depth: i32,
+    pub cause_of_death: Option<String>,
+    /// Set for good once the player picks up the artifact; from then on the run is
+    /// about getting back up.
+    #[serde(default)]
+    pub artifact_recovered: bool,
+    /// Depths whose boss is dead. Their arenas come back empty and open when revisited.
+    #[serde(default)]
+    pub cleared_boss_depths: Vec<i32>,
+}
+
+impl RunStats {
+    pub fn new() -> RunStats {
+        RunStats::default()
+    }
+
+    pub fn boss_cleared(&self, depth: i32) -> bool {
+        self.cleared_boss_depths.contains(&depth)
+    }
+
+    pub fn total_kills(&self) -> i32 {
+        self.kills.values().sum()
+    }
+
+    pub fn score(&self, gold: i32, won: bool) -> i32 {
+        let bonus = if won { VICTORY_BONUS } else { 0 };
+        self.deepest_depth * 100 + self.total_kills() * 10 + gold + bonus
+    }
+}
+
+/// What killed the player, for the game over screen and the high score table.
+pub fn describe_killer(ecs: &World, killer: Option<Entity>) -> String {
+    let player_entity = *ecs.fetch::<Entity>();
+    match killer {
+        Some(killer) if killer == player_entity => "your own hand".to_string(),
+        Some(killer) => ecs
+            .read_storage::<Name>()
+            .get(killer)
+            .map_or("something unseen".to_string(), |name| {
+                format!("a {}", name.name)
+            }),
+        None => {
+            let starving = ecs
+                .read_storage::<HungerClock>()
+                .get(player_entity)
+                .is_some_and(|clock| clock.state == HungerState::Starving);
+            if starving {
+                "starvation".to_string()
+            } else {
+                "something unseen".to_string()
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HighScore {
+    pub score: i32,
+    pub depth: i32,
+    pub turns: i32,
+    /// How the run ended, e.g. "Killed by a Goblin on depth 3".
+    pub fate: String,
+}
+
+/// The best runs so far, highest score first. An unreadable file counts as no runs at all.
+pub fn load_high_scores() -> Vec<HighScore> {
+    std::fs::read_to_string(HIGH_SCORES_FILE)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Adds the run that just ended to the high score table on disk.
+pub fn record_run(ecs: &World, won: bool) {
+    let stats = ecs.fetch::<RunStats>();
+    let depth = ecs.fetch::<super::Map>().depth;
+    let gold = trade::gold(ecs, *ecs.fetch::<Entity>());
+    let fate = if won {
+        "Escaped with the Heart of the Mountain".to_string()
+    } else {
+        format!(
+            "Killed by {} on depth {}",
+            stats
+                .cause_of_death
+                .clone()
+                .unwrap_or_else(|| "something unseen".to_string()),
+            depth
+        )
+    };
+
+    let mut high_scores = load_high_scores();
+    add_high_score(
+        &mut high_scores,
+        HighScore {
+            score: stats.score(gold, won),
+            depth: stats.deepest_depth,
+            turns: stats.turns,
+            fate,
+        },
+    );
+
+    if let Ok(writer) = File::create(HIGH_SCORES_FILE) {
+        serde_json::to_writer(writer, &high_scores).expect("Unable to write high scores");
+    }
+}
+
+/// Puts `entry` in its place in the table, dropping whatever falls off the bottom.
+/// A tie goes to the run that got there first.
+fn add_high_score(high_scores: &mut Vec<HighScore>, entry: HighScore) {
+    high_scores.push(entry);
+    high_scores.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+    high_scores.truncate(HIGH_SCORES_KEPT);
+}
+
+/// The score of the run in progress, as it would be if it ended right now.
+pub fn current_score(ecs: &World) -> i32 {
+    let gold = trade::gold(ecs, *ecs.fetch::<Entity>());
+    let won = ecs.fetch::<super::Map>().depth == 0 && artifact_recovered(ecs);
+    ecs.fetch::<RunStats>().score(gold, won)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn high_score(score: i32, fate: &str) -> HighScore {
+        HighScore {
+            score,
+            depth: 1,
+            turns: 100,
+            fate: fate.to_string(),
+        }
+    }
+
+    #[test]
+    fn score_counts_depth_kills_and_gold() {
+        let mut stats = RunStats::new();
+        stats.deepest_depth = 3;
+        stats.kills.insert("Goblin".to_string(), 4);
+        stats.kills.insert("Orc".to_string(), 1);
+        assert_eq!(stats.score(25, false), 300 + 50 + 25);
+    }
+
+    #[test]
+    fn winning_adds_the_victory_bonus() {
+        let mut stats = RunStats::new();
+        stats.deepest_depth = 5;
+        assert_eq!(stats.score(0, true), 500 + VICTORY_BONUS);
+        assert_eq!(stats.score(0, true) - stats.score(0, false), VICTORY_BONUS);
+    }
+
+    #[test]
+    fn high_scores_are_kept_best_first() {
+        let mut high_scores = vec![high_score(500, "first"), high_score(100, "second")];
+        add_high_score(&mut high_scores, high_score(300, "new"));
+        let scores: Vec<i32> = high_scores.iter().map(|entry| entry.score).collect();
+        assert_eq!(scores, vec![500, 300, 100]);
+    }
+
+    #[test]
+    fn ties_go_to_the_older_run() {
+        let mut high_scores = vec![high_score(200, "old")];
+        add_high_score(&mut high_scores, high_score(200, "new"));
+        assert_eq!(high_scores[0].fate, "old");
+        assert_eq!(high_scores[1].fate, "new");
+    }
+
+    #[test]
+    fn only_the_best_runs_are_kept() {
+        let mut high_scores: Vec<HighScore> = (1..=HIGH_SCORES_KEPT as i32)
+            .map(|score| high_score(score * 10, "old"))
+            .collect();
+        add_high_score(&mut high_scores, high_score(5, "worst"));
+        assert_eq!(high_scores.len(), HIGH_SCORES_KEPT);
+        assert!(high_scores.iter().all(|entry| entry.fate != "worst"));
+
+        add_high_score(&mut high_scores, high_score(1000, "best"));
+        assert_eq!(high_scores.len(), HIGH_SCORES_KEPT);
+        assert_eq!(high_scores[0].fate, "best");
+        assert!(high_scores.iter().all(|entry| entry.score != 10));
+    }
+}
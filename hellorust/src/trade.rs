@@ -0,0 +1,85 @@
+use super::{
+    gamelog::GameLog, identification::item_display_name, Artifact, InBackpack, Item, Vendor,
+    VendorCategory, Wallet,
+};
+use specs::prelude::*;
+
+/// What a vendor charges on top of an item's value, in percent.
+fn markup_percent(category: VendorCategory) -> i32 {
+    match category {
+        VendorCategory::General => 150,
+        VendorCategory::Weaponsmith => 125,
+        VendorCategory::Alchemist => 140,
+    }
+}
+
+fn item_value(ecs: &World, item: Entity) -> i32 {
+    ecs.read_storage::<Item>()
+        .get(item)
+        .map_or(0, |item| item.value)
+}
+
+pub fn buy_price(ecs: &World, vendor: Entity, item: Entity) -> i32 {
+    let markup = ecs
+        .read_storage::<Vendor>()
+        .get(vendor)
+        .map_or(100, |vendor| markup_percent(vendor.category));
+    i32::max(1, item_value(ecs, item) * markup / 100)
+}
+
+/// Vendors buy everything at half of what it's worth.
+pub fn sell_price(ecs: &World, item: Entity) -> i32 {
+    i32::max(1, item_value(ecs, item) / 2)
+}
+
+pub fn gold(ecs: &World, owner: Entity) -> i32 {
+    ecs.read_storage::<Wallet>()
+        .get(owner)
+        .map_or(0, |wallet| wallet.gold)
+}
+
+fn add_gold(ecs: &mut World, owner: Entity, amount: i32) {
+    if let Some(wallet) = ecs.write_storage::<Wallet>().get_mut(owner) {
+        wallet.gold += amount;
+    }
+}
+
+fn change_owner(ecs: &mut World, item: Entity, owner: Entity) {
+    ecs.write_storage::<InBackpack>()
+        .insert(item, InBackpack { owner })
+        .expect("Unable to insert backpack entry");
+}
+
+pub fn buy_item(ecs: &mut World, vendor: Entity, item: Entity) {
+    let player_entity = *ecs.fetch::<Entity>();
+    let price = buy_price(ecs, vendor, item);
+    let item_name = item_display_name(ecs, item);
+    if gold(ecs, player_entity) < price {
+        ecs.fetch_mut::<GameLog>().add(format!(
+            "You can't afford the {} ({} gold).",
+            item_name, price
+        ));
+        return;
+    }
+    add_gold(ecs, player_entity, -price);
+    change_owner(ecs, item, player_entity);
+    ecs.fetch_mut::<GameLog>()
+        .add(format!("You buy the {} for {} gold.", item_name, price));
+}
+
+pub fn sell_item(ecs: &mut World, vendor: Entity, item: Entity) {
+    let player_entity = *ecs.fetch::<Entity>();
+    let price = sell_price(ecs, item);
+    let item_name = item_display_name(ecs, item);
+    if ecs.read_storage::<Artifact>().contains(item) {
+        ecs.fetch_mut::<GameLog>().add(format!(
+            "The {} is not for sale, whatever they offer.",
+            item_name
+        ));
+        return;
+    }
+    add_gold(ecs, player_entity, price);
+    change_owner(ecs, item, vendor);
+    ecs.fetch_mut::<GameLog>()
+        .add(format!("You sell the {} for {} gold.", item_name, price));
+}
@@ -1,9 +1,8 @@
 extern crate specs;
 use super::{
-    EntityMoved, EntryTrigger, Hidden, InflictsDamage, Map, Name, ParticleBuilder, Position,
-    SingleActivation, SufferDamage,
+    effects::{EffectQueue, EffectType, Targets},
+    EntityMoved, EntryTrigger, Hidden, Map, Name, Position,
 };
-use crate::color::*;
 use crate::gamelog::GameLog;
 use specs::prelude::*;
 
@@ -15,14 +14,11 @@ impl<'a> System<'a> for TriggerSystem {
         WriteStorage<'a, EntityMoved>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, EntryTrigger>,
-        ReadStorage<'a, InflictsDamage>,
-        WriteExpect<'a, ParticleBuilder>,
-        WriteStorage<'a, SufferDamage>,
         WriteStorage<'a, Hidden>,
         ReadStorage<'a, Name>,
         Entities<'a>,
         WriteExpect<'a, GameLog>,
-        ReadStorage<'a, SingleActivation>,
+        WriteExpect<'a, EffectQueue>,
     );
     // Iterate the entities that moved and their final position
     fn run(&mut self, data: Self::SystemData) {
@@ -31,17 +27,13 @@ impl<'a> System<'a> for TriggerSystem {
             mut entity_moved,
             position,
             entry_trigger,
-            inflicts_damage,
-            mut particle_builder,
-            mut inflict_damage,
             mut hidden,
             names,
             entities,
             mut log,
-            single_activation,
+            mut effects,
         ) = data;
 
-        let mut remove_entities: Vec<Entity> = Vec::new();
         // Iterate the entities that moved and their final position
         for (entity, mut _entity_moved, position) in
             (&entities, &mut entity_moved, &position).join()
@@ -57,32 +49,21 @@ impl<'a> System<'a> for TriggerSystem {
                             // We triggered it
                             let name = names.get(*entity_id);
                             if let Some(name) = name {
-                                log.entries.push(format!("{} triggers!", &name.name));
+                                log.add(format!("{} triggers!", &name.name));
                             }
                             hidden.remove(*entity_id);
-                            let damage = inflicts_damage.get(*entity_id);
-                            if let Some(damage) = damage {
-                                particle_builder.request(
-                                    position.x,
-                                    position.y,
-                                    orange(),
-                                    black(),
-                                    rltk::to_cp437('‼'),
-                                    200.0,
-                                );
-                                SufferDamage::new_damage(&mut inflict_damage, entity, damage.damage)
-                            }
-                            let single = single_activation.get(*entity_id);
-                            if let Some(_single) = single {
-                                remove_entities.push(*entity_id);
-                            }
+                            // the trap's own components decide what happens to the victim
+                            effects.add_effect(
+                                Some(*entity_id),
+                                EffectType::TriggerFire {
+                                    trigger: *entity_id,
+                                },
+                                Targets::Single { target: entity },
+                            );
                         }
                     }
                 }
             }
-            for trap in remove_entities.iter() {
-                entities.delete(*trap).expect("Unable to delete trap");
-            }
         }
         // this clear() is on the EntityMoved "storage" from systemdata
         entity_moved.clear();
@@ -29,7 +29,7 @@ impl<'a> System<'a> for VisibilitySystem {
         if env::var("MAP_HACK").is_ok() {
             map.visible_tiles.iter_mut().for_each(|t| *t = true);
             map.revealed_tiles.iter_mut().for_each(|t| *t = true);
-            return ();
+            return;
         }
         for (ent, viewshed, pos) in (&entities, &mut viewshed, &pos).join() {
             if viewshed.dirty {
@@ -42,32 +42,28 @@ impl<'a> System<'a> for VisibilitySystem {
 
                 // If this is the player, reveal what they can see
                 let _p: Option<&Player> = player.get(ent);
-                match _p {
-                    Some(_p) => {
-                        for t in map.visible_tiles.iter_mut() {
-                            *t = false;
-                        }
+                if let Some(_p) = _p {
+                    for t in map.visible_tiles.iter_mut() {
+                        *t = false;
+                    }
 
-                        for tile in viewshed.visible_tiles.iter() {
-                            let idx = map.xy_idx(tile.x, tile.y);
-                            map.revealed_tiles[idx] = true;
-                            map.visible_tiles[idx] = true;
-                            for entity in map.tile_content[idx].iter() {
-                                let maybe_hidden = hidden.get(*entity);
-                                if let Some(_maybe_hidden) = maybe_hidden {
-                                    if rng.roll_dice(1, 24) == 1 {
-                                        let maybe_name = name.get(*entity);
-                                        if let Some(name) = maybe_name {
-                                            log.entries
-                                                .push(format!("You spotted a {}.", &name.name));
-                                        }
-                                        hidden.remove(*entity);
+                    for tile in viewshed.visible_tiles.iter() {
+                        let idx = map.xy_idx(tile.x, tile.y);
+                        map.revealed_tiles[idx] = true;
+                        map.visible_tiles[idx] = true;
+                        for entity in map.tile_content[idx].iter() {
+                            let maybe_hidden = hidden.get(*entity);
+                            if let Some(_maybe_hidden) = maybe_hidden {
+                                if rng.roll_dice(1, 24) == 1 {
+                                    let maybe_name = name.get(*entity);
+                                    if let Some(name) = maybe_name {
+                                        log.add(format!("You spotted a {}.", &name.name));
                                     }
+                                    hidden.remove(*entity);
                                 }
                             }
                         }
                     }
-                    None => (),
                 }
             }
         }